@@ -0,0 +1,392 @@
+use flowfine::config::{FilenameConvention, UnrecognizedFiles, VersionFormatting};
+use flowfine::migration::parser::load_migrations;
+use flowfine::migration::source::{DirectorySource, MigrationSource};
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use std::env;
+use std::path::{Path, PathBuf};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{bracketed, parse_macro_input, Ident, LitStr, Token};
+
+/// Embeds the migration files of a directory and its subdirectories, relative to the crate's
+/// `Cargo.toml`, into a `flowfine::migration::embedded::EmbeddedMigrations`, failing
+/// compilation when any of them cannot be parsed:
+///
+/// ```ignore
+/// static MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+/// static DATETIME_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations", datetime);
+/// static SEMVER_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations", semver);
+/// static CUSTOM_MIGRATIONS: EmbeddedMigrations = embed_migrations!(
+///     "migrations",
+///     versioned_prefix = "M",
+///     separator = "-",
+///     suffixes = [".cql", ".sql"],
+///     unrecognized_files = ignore,
+/// );
+/// ```
+///
+/// Filenames follow the default `FilenameConvention` unless `versioned_prefix`, `undo_prefix`,
+/// `repeatable_prefix`, `separator` or `suffixes` are given, and unrecognized files fail
+/// compilation unless `unrecognized_files` is `ignore` or `warn`.
+///
+/// Files listed in a `.flowfineignore` of the directory are left out.
+/// Changes to embedded files trigger a rebuild, added or removed files do not.
+#[proc_macro]
+pub fn embed_migrations(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as EmbedInput);
+
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct EmbedInput {
+    directory: LitStr,
+    version_formatting: Option<Ident>,
+    filename_convention: FilenameConvention,
+    unrecognized_files: UnrecognizedFiles,
+}
+
+impl Parse for EmbedInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut embed_input = Self {
+            directory: input.parse()?,
+            version_formatting: None,
+            filename_convention: FilenameConvention::default(),
+            unrecognized_files: UnrecognizedFiles::Error,
+        };
+
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let name = input.parse::<Ident>()?;
+            if !input.peek(Token![=]) {
+                match embed_input.version_formatting {
+                    None => embed_input.version_formatting = Some(name),
+                    Some(_) => return Err(syn::Error::new(name.span(), "expected `=`")),
+                }
+                continue;
+            }
+            input.parse::<Token![=]>()?;
+            embed_input.parse_option(&name, input)?;
+        }
+
+        Ok(embed_input)
+    }
+}
+
+impl EmbedInput {
+    fn parse_option(&mut self, name: &Ident, input: ParseStream) -> syn::Result<()> {
+        let convention = &mut self.filename_convention;
+        match name.to_string().as_str() {
+            "versioned_prefix" => convention.versioned_prefix = input.parse::<LitStr>()?.value(),
+            "undo_prefix" => convention.undo_prefix = input.parse::<LitStr>()?.value(),
+            "repeatable_prefix" => convention.repeatable_prefix = input.parse::<LitStr>()?.value(),
+            "separator" => convention.separator = input.parse::<LitStr>()?.value(),
+            "suffixes" => {
+                let content;
+                bracketed!(content in input);
+                convention.suffixes = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?
+                    .iter()
+                    .map(LitStr::value)
+                    .collect();
+            }
+            "unrecognized_files" => {
+                let value = input.parse::<Ident>()?;
+                self.unrecognized_files = match value.to_string().as_str() {
+                    "ignore" => UnrecognizedFiles::Ignore,
+                    "warn" => UnrecognizedFiles::Warn,
+                    "error" => UnrecognizedFiles::Error,
+                    _ => {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "expected ignore, warn or error",
+                        ))
+                    }
+                };
+            }
+            _ => {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "expected versioned_prefix, undo_prefix, repeatable_prefix, separator, \
+                    suffixes or unrecognized_files",
+                ))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn expand(input: EmbedInput) -> syn::Result<TokenStream2> {
+    let version_formatting = match &input.version_formatting {
+        None => VersionFormatting::Numeric,
+        Some(ident) if ident == "numeric" => VersionFormatting::Numeric,
+        Some(ident) if ident == "datetime" => VersionFormatting::Datetime,
+        Some(ident) if ident == "semver" => VersionFormatting::Semver,
+        Some(ident) if ident == "epoch" => VersionFormatting::Epoch,
+        Some(ident) => {
+            return Err(syn::Error::new(
+                ident.span(),
+                "expected numeric, datetime, semver or epoch",
+            ))
+        }
+    };
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR")
+        .map_err(|err| syn::Error::new(input.directory.span(), err))?;
+    let directory = Path::new(&manifest_dir).join(input.directory.value());
+
+    embed(
+        &directory,
+        version_formatting,
+        &input.filename_convention,
+        input.unrecognized_files,
+    )
+    .map_err(|message| syn::Error::new(input.directory.span(), message))
+}
+
+fn embed(
+    directory: &Path,
+    version_formatting: VersionFormatting,
+    filename_convention: &FilenameConvention,
+    unrecognized_files: UnrecognizedFiles,
+) -> Result<TokenStream2, String> {
+    let source = DirectorySource::new(directory).with_filename_convention(filename_convention);
+    load_migrations(
+        &source,
+        &version_formatting,
+        filename_convention,
+        unrecognized_files,
+    )
+    .map_err(|err| err.to_string())?
+    .into_result()
+    .map_err(|errors| {
+        let errors = errors
+            .iter()
+            .map(|err| format!("  {}", err))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("Migrations could not be parsed:\n{}", errors)
+    })?;
+
+    // unrecognized files are left out, they may not even be UTF-8
+    let mut paths = source
+        .files()
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .filter(|file| filename_convention.suffix(&file.filename).is_some())
+        .map(|file| PathBuf::from(file.path))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let files = paths.iter().map(|path| {
+        let relative_path = path
+            .strip_prefix(directory)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string();
+        let path = path.to_string_lossy().to_string();
+        quote! { (#relative_path, include_str!(#path)) }
+    });
+    let version_formatting = match version_formatting {
+        VersionFormatting::Numeric => quote! { ::flowfine::config::VersionFormatting::Numeric },
+        VersionFormatting::Datetime => quote! { ::flowfine::config::VersionFormatting::Datetime },
+        VersionFormatting::Semver => quote! { ::flowfine::config::VersionFormatting::Semver },
+        VersionFormatting::Epoch => quote! { ::flowfine::config::VersionFormatting::Epoch },
+        VersionFormatting::Custom(_) => {
+            return Err("Custom version formats cannot be embedded".to_string())
+        }
+    };
+
+    if *filename_convention == FilenameConvention::default()
+        && unrecognized_files == UnrecognizedFiles::Error
+    {
+        return Ok(quote! {
+            ::flowfine::migration::embedded::EmbeddedMigrations::new(
+                #version_formatting,
+                &[#(#files),*],
+            )
+        });
+    }
+
+    let FilenameConvention {
+        versioned_prefix,
+        undo_prefix,
+        repeatable_prefix,
+        separator,
+        suffixes,
+    } = filename_convention;
+    let unrecognized_files = match unrecognized_files {
+        UnrecognizedFiles::Ignore => quote! { ::flowfine::config::UnrecognizedFiles::Ignore },
+        UnrecognizedFiles::Warn => quote! { ::flowfine::config::UnrecognizedFiles::Warn },
+        UnrecognizedFiles::Error => quote! { ::flowfine::config::UnrecognizedFiles::Error },
+    };
+
+    Ok(quote! {
+        ::flowfine::migration::embedded::EmbeddedMigrations::with_convention(
+            #version_formatting,
+            ::flowfine::migration::embedded::EmbeddedFilenameConvention {
+                versioned_prefix: #versioned_prefix,
+                undo_prefix: #undo_prefix,
+                repeatable_prefix: #repeatable_prefix,
+                separator: #separator,
+                suffixes: &[#(#suffixes),*],
+            },
+            #unrecognized_files,
+            &[#(#files),*],
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_embed_valid_migrations() {
+        // given
+        let directory = Path::new("../flowfine/tests/data/unit/numeric_migrations");
+
+        // when
+        let result = embed(
+            directory,
+            VersionFormatting::Numeric,
+            &FilenameConvention::default(),
+            UnrecognizedFiles::Error,
+        );
+
+        // then
+        let tokens = result.unwrap().to_string();
+        assert!(tokens.contains("\"V1.1__migration.cql\""));
+        assert!(tokens.contains("include_str !"));
+    }
+
+    #[test]
+    fn test_embed_nested_migrations() {
+        // given
+        let directory = Path::new("../flowfine/tests/data/unit/nested_migrations");
+
+        // when
+        let result = embed(
+            directory,
+            VersionFormatting::Numeric,
+            &FilenameConvention::default(),
+            UnrecognizedFiles::Error,
+        );
+
+        // then
+        let tokens = result.unwrap().to_string();
+        assert!(tokens.contains("\"billing/V2__create_invoices.cql\""));
+    }
+
+    #[test]
+    fn test_reject_invalid_migrations() {
+        // given
+        let directory = Path::new("../flowfine/tests/data/unit/invalid_migrations");
+
+        // when
+        let result = embed(
+            directory,
+            VersionFormatting::Numeric,
+            &FilenameConvention::default(),
+            UnrecognizedFiles::Error,
+        );
+
+        // then
+        let message = result.unwrap_err();
+        assert!(message.contains("V1__invalid_migration_missing_semicolon.cql"));
+    }
+
+    #[test]
+    fn test_embed_migrations_with_custom_convention() {
+        // given
+        let directory = Path::new("../flowfine/tests/data/unit/convention_migrations");
+        let filename_convention = FilenameConvention {
+            versioned_prefix: "M".to_string(),
+            separator: "-".to_string(),
+            suffixes: vec![".sql".to_string()],
+            ..FilenameConvention::default()
+        };
+
+        // when
+        let result = embed(
+            directory,
+            VersionFormatting::Numeric,
+            &filename_convention,
+            UnrecognizedFiles::Error,
+        );
+
+        // then
+        let tokens = result.unwrap().to_string();
+        assert!(tokens.contains("with_convention"));
+        assert!(tokens.contains("versioned_prefix : \"M\""));
+        assert!(tokens.contains("\"M1-create_foo.sql\""));
+    }
+
+    #[rstest(
+        unrecognized_files,
+        expected_result,
+        case(UnrecognizedFiles::Error, false),
+        case(UnrecognizedFiles::Warn, true),
+        case(UnrecognizedFiles::Ignore, true)
+    )]
+    fn test_embed_unrecognized_files(unrecognized_files: UnrecognizedFiles, expected_result: bool) {
+        // given
+        let directory = Path::new("../flowfine/tests/data/unit/ignored_migrations");
+
+        // when
+        let result = embed(
+            directory,
+            VersionFormatting::Numeric,
+            &FilenameConvention::default(),
+            unrecognized_files,
+        );
+
+        // then
+        assert_eq!(result.is_ok(), expected_result);
+    }
+
+    #[test]
+    fn test_leave_out_unrecognized_binary_files() {
+        // given
+        let directory = Path::new("../flowfine/tests/data/unit/binary_migrations");
+
+        // when
+        let result = embed(
+            directory,
+            VersionFormatting::Numeric,
+            &FilenameConvention::default(),
+            UnrecognizedFiles::Ignore,
+        );
+
+        // then
+        let tokens = result.unwrap().to_string();
+        assert!(tokens.contains("\"V1__create_foo.cql\""));
+        assert!(!tokens.contains(".DS_Store"));
+    }
+
+    #[test]
+    fn test_parse_convention_arguments() {
+        // when
+        let input = syn::parse_str::<EmbedInput>(
+            r#""migrations", datetime, versioned_prefix = "M", suffixes = [".cql", ".sql"], unrecognized_files = ignore,"#,
+        )
+        .unwrap();
+
+        // then
+        assert_eq!(input.version_formatting.unwrap(), "datetime");
+        assert_eq!(input.filename_convention.versioned_prefix, "M");
+        assert_eq!(input.filename_convention.suffixes, vec![".cql", ".sql"]);
+        assert_eq!(input.unrecognized_files, UnrecognizedFiles::Ignore);
+    }
+
+    #[test]
+    fn test_reject_unknown_argument() {
+        // when
+        let result = syn::parse_str::<EmbedInput>(r#""migrations", prefix = "M""#);
+
+        // then
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod tests {
+    use flowfine::migration::embedded::EmbeddedMigrations;
+    use flowfine_macros::embed_migrations;
+
+    static MIGRATIONS: EmbeddedMigrations =
+        embed_migrations!("../flowfine/tests/data/int/numeric_migrations");
+
+    static DATETIME_MIGRATIONS: EmbeddedMigrations =
+        embed_migrations!("../flowfine/tests/data/unit/datetime_migrations", datetime);
+
+    static CONVENTION_MIGRATIONS: EmbeddedMigrations = embed_migrations!(
+        "../flowfine/tests/data/unit/convention_migrations",
+        versioned_prefix = "M",
+        separator = "-",
+        suffixes = [".sql"],
+    );
+
+    static IGNORING_MIGRATIONS: EmbeddedMigrations = embed_migrations!(
+        "../flowfine/tests/data/unit/ignored_migrations",
+        unrecognized_files = ignore
+    );
+
+    static BINARY_MIGRATIONS: EmbeddedMigrations = embed_migrations!(
+        "../flowfine/tests/data/unit/binary_migrations",
+        unrecognized_files = ignore
+    );
+
+    #[test]
+    fn test_load_embedded_migrations() {
+        // when
+        let migrations = MIGRATIONS.load().into_result().unwrap();
+
+        // then
+        assert_eq!(migrations.len(), 3);
+        assert!(migrations
+            .iter()
+            .all(|migration| !migration.queries.is_empty()));
+    }
+
+    #[test]
+    fn test_load_embedded_datetime_migrations() {
+        // when
+        let migrations = DATETIME_MIGRATIONS.load().into_result().unwrap();
+
+        // then
+        assert_eq!(migrations[0].version, "20230903141500");
+    }
+
+    #[test]
+    fn test_load_embedded_migrations_with_custom_convention() {
+        // when
+        let migrations = CONVENTION_MIGRATIONS.load().into_result().unwrap();
+
+        // then
+        let versions = migrations
+            .iter()
+            .map(|migration| migration.version.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(versions, vec!["1", "2"]);
+    }
+
+    #[test]
+    fn test_load_embedded_migrations_ignoring_unrecognized_files() {
+        // when
+        let result = IGNORING_MIGRATIONS.load();
+
+        // then
+        assert_eq!(result.into_result().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_load_embedded_migrations_next_to_binary_files() {
+        // when
+        let migrations = BINARY_MIGRATIONS.load().into_result().unwrap();
+
+        // then
+        assert_eq!(migrations.len(), 1);
+    }
+}
@@ -0,0 +1,279 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use flowfine::config::loader::{
+    ConfigError, PartialConnectionConfig, PartialKeyspaceDefinition, PartialMigrationConfig,
+    PartialTlsConfig,
+};
+use flowfine::config::MigrationConfig;
+use std::env;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    name = "flowfine",
+    version,
+    about = "ScyllaDB and Cassandra migration tool"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Applies pending migrations.
+    Migrate(TargetArgs),
+
+    /// Lists migrations together with their state.
+    Info(TargetArgs),
+
+    /// Verifies applied migrations against the local files.
+    Validate(TargetArgs),
+
+    /// Marks an existing keyspace as migrated up to a version.
+    Baseline {
+        #[command(flatten)]
+        target: TargetArgs,
+
+        #[arg(long = "baseline-version", default_value = "1")]
+        version: String,
+
+        #[arg(long, default_value = "Flowfine Baseline")]
+        description: String,
+    },
+
+    /// Removes failed history entries and realigns checksums with the local files.
+    Repair(TargetArgs),
+
+    /// Renames unapplied migrations whose version is applied, duplicated or older than the
+    /// latest applied one, giving them versions after it.
+    Rebase {
+        #[command(flatten)]
+        target: TargetArgs,
+
+        /// Lists the renames without renaming any file.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Drops the keyspace together with its history.
+    Clean {
+        #[command(flatten)]
+        target: TargetArgs,
+
+        /// Confirms that the keyspace should be dropped.
+        #[arg(long)]
+        yes: bool,
+    },
+
+    /// Creates an empty migration file with the next version in the first location.
+    New {
+        #[command(flatten)]
+        source: SourceArgs,
+
+        /// Name of the migration, e.g. `create_users`.
+        name: String,
+    },
+}
+
+#[derive(Args)]
+pub struct ConfigArgs {
+    /// Configuration file, `flowfine.toml` is used when present.
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Profile of the configuration file, defaults to `FLOWFINE_PROFILE`.
+    #[arg(long)]
+    pub profile: Option<String>,
+}
+
+#[derive(Args)]
+pub struct SourceArgs {
+    #[command(flatten)]
+    pub config: ConfigArgs,
+
+    /// Directory scanned recursively for migration files, can be repeated.
+    #[arg(long = "location", alias = "directory")]
+    pub locations: Vec<String>,
+
+    #[arg(long, value_enum)]
+    pub version_formatting: Option<VersionFormattingArg>,
+
+    /// chrono format of custom versions, e.g. `%Y_%m_%d_%H%M`.
+    #[arg(long)]
+    pub version_format: Option<String>,
+
+    /// Regex of custom versions whose capture groups are compared in order.
+    #[arg(long)]
+    pub version_pattern: Option<String>,
+
+    /// Glob of files to skip, e.g. `*.md` or `drafts/*`, can be repeated.
+    #[arg(long)]
+    pub ignore: Vec<String>,
+
+    /// Handling of files without the `.cql` extension.
+    #[arg(long, value_enum)]
+    pub unrecognized_files: Option<UnrecognizedFilesArg>,
+}
+
+#[derive(Args)]
+pub struct TargetArgs {
+    #[command(flatten)]
+    pub connection: ConnectionArgs,
+
+    #[command(flatten)]
+    pub source: SourceArgs,
+
+    #[arg(long)]
+    pub keyspace: Option<String>,
+
+    /// Creates the keyspace with SimpleStrategy and this replication factor when it is missing.
+    #[arg(long)]
+    pub replication_factor: Option<u32>,
+
+    /// Overall time budget of a run, in seconds.
+    #[arg(long)]
+    pub run_deadline: Option<u64>,
+
+    /// Time budget of a single migration, in seconds.
+    #[arg(long)]
+    pub migration_budget: Option<u64>,
+}
+
+#[derive(Args)]
+pub struct ConnectionArgs {
+    /// Contact point, can be repeated.
+    #[arg(long = "node")]
+    pub nodes: Vec<String>,
+
+    #[arg(long)]
+    pub user: Option<String>,
+
+    #[arg(long)]
+    pub password: Option<String>,
+
+    /// Datacenter whose nodes receive the requests.
+    #[arg(long)]
+    pub local_datacenter: Option<String>,
+
+    /// Encrypts connections, implied by the other TLS flags.
+    #[cfg(feature = "tls")]
+    #[arg(long)]
+    pub tls: bool,
+
+    /// CA certificate verifying the cluster, the system certificates are used otherwise.
+    #[cfg(feature = "tls")]
+    #[arg(long)]
+    pub tls_ca_file: Option<PathBuf>,
+
+    #[cfg(feature = "tls")]
+    #[arg(long)]
+    pub tls_cert_file: Option<PathBuf>,
+
+    #[cfg(feature = "tls")]
+    #[arg(long)]
+    pub tls_key_file: Option<PathBuf>,
+}
+
+impl ConnectionArgs {
+    #[cfg(feature = "tls")]
+    fn tls_config(&self) -> PartialTlsConfig {
+        PartialTlsConfig {
+            enabled: self.tls.then_some(true),
+            ca_file: self.tls_ca_file.clone(),
+            cert_file: self.tls_cert_file.clone(),
+            key_file: self.tls_key_file.clone(),
+        }
+    }
+
+    /// Without the tls feature TLS can still be configured by file or environment, connecting
+    /// then fails with `SessionError::TlsUnsupportedError`.
+    #[cfg(not(feature = "tls"))]
+    fn tls_config(&self) -> PartialTlsConfig {
+        PartialTlsConfig::default()
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum VersionFormattingArg {
+    Numeric,
+    Datetime,
+    Semver,
+    Epoch,
+    Custom,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum UnrecognizedFilesArg {
+    Ignore,
+    Warn,
+    Error,
+}
+
+impl SourceArgs {
+    /// Layers flags over `FLOWFINE_*` variables over the configuration file.
+    pub fn to_partial_config(&self) -> Result<PartialMigrationConfig, ConfigError> {
+        let profile = self
+            .config
+            .profile
+            .clone()
+            .or_else(|| env::var("FLOWFINE_PROFILE").ok());
+        let path = self.config.config.clone().or_else(|| {
+            let default_path = PathBuf::from("flowfine.toml");
+            default_path.exists().then_some(default_path)
+        });
+
+        let file = match path {
+            Some(path) => PartialMigrationConfig::from_file(path, profile.as_deref())?,
+            None => PartialMigrationConfig::default(),
+        };
+        let flags = PartialMigrationConfig {
+            locations: (!self.locations.is_empty()).then(|| self.locations.clone()),
+            version_formatting: self.version_formatting.map(|version_formatting| {
+                match version_formatting {
+                    VersionFormattingArg::Numeric => "numeric".to_string(),
+                    VersionFormattingArg::Datetime => "datetime".to_string(),
+                    VersionFormattingArg::Semver => "semver".to_string(),
+                    VersionFormattingArg::Epoch => "epoch".to_string(),
+                    VersionFormattingArg::Custom => "custom".to_string(),
+                }
+            }),
+            version_format: self.version_format.clone(),
+            version_pattern: self.version_pattern.clone(),
+            ignore: (!self.ignore.is_empty()).then(|| self.ignore.clone()),
+            unrecognized_files: self.unrecognized_files.map(|unrecognized_files| {
+                match unrecognized_files {
+                    UnrecognizedFilesArg::Ignore => "ignore".to_string(),
+                    UnrecognizedFilesArg::Warn => "warn".to_string(),
+                    UnrecognizedFilesArg::Error => "error".to_string(),
+                }
+            }),
+            ..PartialMigrationConfig::default()
+        };
+
+        Ok(file.merge(PartialMigrationConfig::from_env()?).merge(flags))
+    }
+}
+
+impl TargetArgs {
+    pub fn to_config(&self) -> Result<MigrationConfig, ConfigError> {
+        let flags = PartialMigrationConfig {
+            keyspace: self.keyspace.clone(),
+            connection: PartialConnectionConfig {
+                nodes: (!self.connection.nodes.is_empty()).then(|| self.connection.nodes.clone()),
+                username: self.connection.user.clone(),
+                password: self.connection.password.clone(),
+                local_datacenter: self.connection.local_datacenter.clone(),
+                tls: self.connection.tls_config(),
+            },
+            keyspace_definition: PartialKeyspaceDefinition {
+                replication_factor: self.replication_factor,
+                ..PartialKeyspaceDefinition::default()
+            },
+            run_deadline_seconds: self.run_deadline,
+            migration_budget_seconds: self.migration_budget,
+            ..PartialMigrationConfig::default()
+        };
+
+        self.source.to_partial_config()?.merge(flags).build()
+    }
+}
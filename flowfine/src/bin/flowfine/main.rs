@@ -0,0 +1,220 @@
+mod cli;
+
+use crate::cli::{Cli, Command, SourceArgs, TargetArgs};
+use clap::Parser;
+use flowfine::config::loader::ConfigError;
+use flowfine::migration::parser::load_migrations;
+use flowfine::migration::source::DirectorySource;
+use flowfine::migration::version::next_version;
+use flowfine::migration::MigrationKind;
+use flowfine::runner::plan::PlanAction;
+use flowfine::runner::MigrationExecutionError;
+use flowfine::{Flowfine, FlowfineError};
+use std::path::Path;
+use std::process::ExitCode;
+use std::{fs, io};
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+
+/// Content of new migration files, a harmless statement to be replaced.
+const MIGRATION_TEMPLATE: &str =
+    "-- Replace with the statements of this migration.\nSELECT now() FROM system.local;\n";
+
+/// Exit codes: 1 for execution errors, 2 for invalid arguments or configuration,
+/// 3 for unparsable migration files and 4 for migrations that differ from the applied ones.
+#[derive(Debug, Error)]
+enum CliError {
+    #[error("Invalid configuration: {0}")]
+    Config(#[from] ConfigError),
+
+    #[error(transparent)]
+    Flowfine(#[from] FlowfineError),
+
+    #[error("Validation failed: {0}")]
+    Validation(FlowfineError),
+
+    #[error("Clean drops keyspace {0}, pass --yes to confirm")]
+    UnconfirmedClean(String),
+
+    #[error("Migration file could not be created: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Next version cannot be derived from a custom version pattern")]
+    UnderivableVersion,
+}
+
+impl CliError {
+    fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Config(_) => 2,
+            CliError::Flowfine(FlowfineError::ParseError(_))
+            | CliError::Validation(FlowfineError::ParseError(_)) => 3,
+            CliError::Validation(FlowfineError::ExecutionError(
+                MigrationExecutionError::ChecksumMismatchError(_)
+                | MigrationExecutionError::MissingAppliedMigrationError(_),
+            )) => 4,
+            _ => 1,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(cli.command).await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::from(err.exit_code())
+        }
+    }
+}
+
+async fn run(command: Command) -> Result<(), CliError> {
+    match command {
+        Command::Migrate(target) => {
+            let applied_migrations = flowfine(&target)?
+                .migrate_with_cancellation(shutdown_token())
+                .await?;
+            println!("Applied {} migrations", applied_migrations.len());
+        }
+        Command::Info(target) => {
+            let plan = flowfine(&target)?.info().await?;
+            for planned in &plan.actions {
+                println!(
+                    "{:<16} {:<40} {:<11} {}",
+                    planned.version,
+                    planned.name,
+                    format!("{:?}", planned.kind),
+                    describe_action(planned.kind, planned.action)
+                );
+            }
+        }
+        Command::Validate(target) => {
+            let flowfine = flowfine(&target)?;
+            flowfine.validate().await.map_err(CliError::Validation)?;
+            println!("Validated {} migrations", flowfine.migrations()?.len());
+        }
+        Command::Baseline {
+            target,
+            version,
+            description,
+        } => {
+            flowfine(&target)?.baseline(&version, &description).await?;
+            println!("Baselined at version {}", version);
+        }
+        Command::Repair(target) => {
+            for filename in flowfine(&target)?.repair().await? {
+                println!("Realigned checksum of {}", filename);
+            }
+        }
+        Command::Rebase { target, dry_run } => {
+            let plan = flowfine(&target)?.rebase().await?;
+            for file in plan.renames.iter().flat_map(|rename| &rename.files) {
+                println!("{} -> {}", file.from, file.to);
+            }
+            if !dry_run {
+                plan.apply().map_err(FlowfineError::from)?;
+                println!("Rebased {} migrations", plan.renames.len());
+            }
+        }
+        Command::Clean { target, yes } => {
+            let flowfine = flowfine(&target)?;
+            if !yes {
+                let keyspace = flowfine.config().keyspace.clone();
+                return Err(CliError::UnconfirmedClean(keyspace));
+            }
+            flowfine.clean().await?;
+            println!("Dropped keyspace {}", flowfine.config().keyspace);
+        }
+        Command::New { source, name } => {
+            let path = create_migration_file(&source, &name)?;
+            println!("Created {}", path);
+        }
+    }
+
+    Ok(())
+}
+
+fn flowfine(target: &TargetArgs) -> Result<Flowfine, CliError> {
+    Ok(Flowfine::new(target.to_config()?))
+}
+
+fn create_migration_file(source: &SourceArgs, name: &str) -> Result<String, CliError> {
+    let config = source.to_partial_config()?;
+    let locations = config.locations()?;
+    let version_formatting = config.version_formatting()?;
+    let ignore = config.ignore()?;
+    let filename_convention = config.filenames.build()?;
+    let sources = locations
+        .iter()
+        .map(|location| {
+            DirectorySource::new(location)
+                .with_ignore_patterns(&ignore)
+                .with_filename_convention(&filename_convention)
+        })
+        .collect::<Vec<_>>();
+    let migrations = load_migrations(
+        &sources,
+        &version_formatting,
+        &filename_convention,
+        config.unrecognized_files()?,
+    )
+    .map_err(FlowfineError::from)?
+    .into_result()
+    .map_err(FlowfineError::ParseError)?;
+
+    let version = next_version(
+        &version_formatting,
+        migrations
+            .iter()
+            .filter(|migration| migration.kind == MigrationKind::Versioned)
+            .map(|migration| migration.version.as_str()),
+    )
+    .ok_or(CliError::UnderivableVersion)?;
+    let filename = filename_convention.versioned_filename(&version, name);
+    let path = Path::new(&locations[0]).join(filename);
+
+    fs::write(&path, MIGRATION_TEMPLATE)?;
+    Ok(path.display().to_string())
+}
+
+fn describe_action(kind: MigrationKind, action: PlanAction) -> &'static str {
+    match (kind, action) {
+        (_, PlanAction::Apply) => "Pending",
+        (_, PlanAction::Reapply) => "Outdated",
+        (_, PlanAction::Undo) => "Undo pending",
+        (MigrationKind::Versioned, PlanAction::Skip) => "Applied",
+        (_, PlanAction::Skip) => "Up to date",
+    }
+}
+
+/// Cancels the run on Ctrl+C or SIGTERM, letting the runner record an interrupted migration.
+fn shutdown_token() -> CancellationToken {
+    let cancellation = CancellationToken::new();
+    let token = cancellation.clone();
+
+    tokio::spawn(async move {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            let mut terminate = match signal(SignalKind::terminate()) {
+                Ok(terminate) => terminate,
+                Err(_) => return,
+            };
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = tokio::signal::ctrl_c().await;
+
+        eprintln!("Shutdown requested, stopping after the current statement");
+        token.cancel();
+    });
+
+    cancellation
+}
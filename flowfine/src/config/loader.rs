@@ -0,0 +1,842 @@
+use crate::config::loader::ConfigError::*;
+use crate::config::*;
+use chrono::format::{Item, StrftimeItems};
+use glob::Pattern;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{env, fs, io};
+use thiserror::Error;
+
+lazy_static! {
+    static ref KEYSPACE_NAME_REGEX: Regex = Regex::new(r"^[A-Za-z0-9_]{1,48}$").unwrap();
+    static ref PROFILE_ENV_VAR: &'static str = "FLOWFINE_PROFILE";
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Configuration file {0} could not be read: {1}")]
+    ReadError(String, io::Error),
+
+    #[error("Configuration file {0} is invalid: {1}")]
+    ParseError(String, toml::de::Error),
+
+    #[error("Profile {0} is not defined")]
+    UnknownProfileError(String),
+
+    #[error("Missing value for {0}")]
+    MissingValueError(String),
+
+    #[error("Invalid value for {key}: {message}")]
+    InvalidValueError { key: String, message: String },
+}
+
+/// One layer of settings, e.g. the base of `flowfine.toml`, one of its `[profiles.<name>]`
+/// tables or the `FLOWFINE_*` environment variables. Later layers win when merged.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartialMigrationConfig {
+    pub locations: Option<Vec<String>>,
+    pub ignore: Option<Vec<String>>,
+    pub unrecognized_files: Option<String>,
+    #[serde(default)]
+    pub filenames: PartialFilenameConvention,
+    pub version_formatting: Option<String>,
+    /// chrono format of `custom` versions, e.g. `%Y_%m_%d_%H%M`.
+    pub version_format: Option<String>,
+    /// Regex of `custom` versions whose capture groups are compared in order.
+    pub version_pattern: Option<String>,
+    pub keyspace: Option<String>,
+    #[serde(default)]
+    pub connection: PartialConnectionConfig,
+    #[serde(default)]
+    pub keyspace_definition: PartialKeyspaceDefinition,
+    pub run_deadline_seconds: Option<u64>,
+    pub migration_budget_seconds: Option<u64>,
+    pub target: Option<String>,
+    pub executed_by: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartialConnectionConfig {
+    pub nodes: Option<Vec<String>>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub local_datacenter: Option<String>,
+    #[serde(default)]
+    pub tls: PartialTlsConfig,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartialTlsConfig {
+    /// Enables TLS without any file, defaults to whether one of the files is set.
+    pub enabled: Option<bool>,
+    pub ca_file: Option<PathBuf>,
+    pub cert_file: Option<PathBuf>,
+    pub key_file: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartialFilenameConvention {
+    pub versioned_prefix: Option<String>,
+    pub undo_prefix: Option<String>,
+    pub repeatable_prefix: Option<String>,
+    pub separator: Option<String>,
+    pub suffixes: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PartialKeyspaceDefinition {
+    pub class: Option<String>,
+    pub replication_factor: Option<u32>,
+    pub datacenters: Option<BTreeMap<String, u32>>,
+    pub durable_writes: Option<bool>,
+}
+
+impl MigrationConfig {
+    /// Loads `path` with the given profile, falling back to `FLOWFINE_PROFILE`,
+    /// and applies `FLOWFINE_*` environment overrides.
+    pub fn load(path: impl AsRef<Path>, profile: Option<&str>) -> Result<Self, ConfigError> {
+        let profile = profile
+            .map(str::to_string)
+            .or_else(|| env::var(*PROFILE_ENV_VAR).ok());
+
+        PartialMigrationConfig::from_file(path, profile.as_deref())?
+            .merge(PartialMigrationConfig::from_env()?)
+            .build()
+    }
+
+    pub fn from_env() -> Result<Self, ConfigError> {
+        PartialMigrationConfig::from_env()?.build()
+    }
+}
+
+impl PartialMigrationConfig {
+    pub fn from_file(path: impl AsRef<Path>, profile: Option<&str>) -> Result<Self, ConfigError> {
+        let path = path.as_ref().display().to_string();
+        let content = fs::read_to_string(&path).map_err(|err| ReadError(path.clone(), err))?;
+
+        Self::from_toml(&path, &content, profile)
+    }
+
+    /// Parses the base settings of a TOML document and merges the selected profile over them.
+    pub fn from_toml(
+        source: &str,
+        content: &str,
+        profile: Option<&str>,
+    ) -> Result<Self, ConfigError> {
+        let parse_error = |err| ParseError(source.to_string(), err);
+
+        let mut table = content.parse::<toml::Table>().map_err(parse_error)?;
+        let mut profiles = match table.remove("profiles") {
+            Some(toml::Value::Table(profiles)) => profiles,
+            Some(_) => return Err(invalid("profiles", "expected a table of profiles")),
+            None => toml::Table::new(),
+        };
+        let base = Self::deserialize(table).map_err(parse_error)?;
+
+        match profile {
+            Some(profile) => {
+                let overrides = profiles
+                    .remove(profile)
+                    .ok_or(UnknownProfileError(profile.to_string()))?;
+                Ok(base.merge(Self::deserialize(overrides).map_err(parse_error)?))
+            }
+            None => Ok(base),
+        }
+    }
+
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_env_vars(|key| env::var(key).ok())
+    }
+
+    /// Reads `FLOWFINE_*` variables through `lookup`; list values are comma-separated and
+    /// datacenters are given as `dc1:3,dc2:2`.
+    pub fn from_env_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let list = |key: &str| {
+            lookup(key).map(|value| {
+                value
+                    .split(',')
+                    .map(|item| item.trim().to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        let datacenters = list("FLOWFINE_REPLICATION_DATACENTERS")
+            .map(|datacenters| {
+                datacenters
+                    .iter()
+                    .map(|datacenter| {
+                        let (name, replication_factor) =
+                            datacenter.split_once(':').ok_or_else(|| {
+                                invalid("FLOWFINE_REPLICATION_DATACENTERS", "expected dc:factor")
+                            })?;
+                        let replication_factor = parse_value::<u32>(
+                            "FLOWFINE_REPLICATION_DATACENTERS",
+                            replication_factor,
+                        )?;
+                        Ok((name.to_string(), replication_factor))
+                    })
+                    .collect::<Result<BTreeMap<_, _>, ConfigError>>()
+            })
+            .transpose()?;
+
+        Ok(Self {
+            locations: list("FLOWFINE_LOCATIONS"),
+            ignore: list("FLOWFINE_IGNORE"),
+            unrecognized_files: lookup("FLOWFINE_UNRECOGNIZED_FILES"),
+            filenames: PartialFilenameConvention {
+                versioned_prefix: lookup("FLOWFINE_VERSIONED_PREFIX"),
+                undo_prefix: lookup("FLOWFINE_UNDO_PREFIX"),
+                repeatable_prefix: lookup("FLOWFINE_REPEATABLE_PREFIX"),
+                separator: lookup("FLOWFINE_SEPARATOR"),
+                suffixes: list("FLOWFINE_SUFFIXES"),
+            },
+            version_formatting: lookup("FLOWFINE_VERSION_FORMATTING"),
+            version_format: lookup("FLOWFINE_VERSION_FORMAT"),
+            version_pattern: lookup("FLOWFINE_VERSION_PATTERN"),
+            keyspace: lookup("FLOWFINE_KEYSPACE"),
+            connection: PartialConnectionConfig {
+                nodes: list("FLOWFINE_NODES"),
+                username: lookup("FLOWFINE_USERNAME"),
+                password: lookup("FLOWFINE_PASSWORD"),
+                local_datacenter: lookup("FLOWFINE_LOCAL_DATACENTER"),
+                tls: PartialTlsConfig {
+                    enabled: parse_env(&lookup, "FLOWFINE_TLS")?,
+                    ca_file: lookup("FLOWFINE_TLS_CA_FILE").map(PathBuf::from),
+                    cert_file: lookup("FLOWFINE_TLS_CERT_FILE").map(PathBuf::from),
+                    key_file: lookup("FLOWFINE_TLS_KEY_FILE").map(PathBuf::from),
+                },
+            },
+            keyspace_definition: PartialKeyspaceDefinition {
+                class: lookup("FLOWFINE_REPLICATION_CLASS"),
+                replication_factor: parse_env(&lookup, "FLOWFINE_REPLICATION_FACTOR")?,
+                datacenters,
+                durable_writes: parse_env(&lookup, "FLOWFINE_DURABLE_WRITES")?,
+            },
+            run_deadline_seconds: parse_env(&lookup, "FLOWFINE_RUN_DEADLINE_SECONDS")?,
+            migration_budget_seconds: parse_env(&lookup, "FLOWFINE_MIGRATION_BUDGET_SECONDS")?,
+            target: lookup("FLOWFINE_TARGET"),
+            executed_by: lookup("FLOWFINE_EXECUTED_BY"),
+        })
+    }
+
+    /// Overrides every value of `self` that is set in `other`.
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            locations: other.locations.or(self.locations),
+            ignore: other.ignore.or(self.ignore),
+            unrecognized_files: other.unrecognized_files.or(self.unrecognized_files),
+            filenames: PartialFilenameConvention {
+                versioned_prefix: other
+                    .filenames
+                    .versioned_prefix
+                    .or(self.filenames.versioned_prefix),
+                undo_prefix: other.filenames.undo_prefix.or(self.filenames.undo_prefix),
+                repeatable_prefix: other
+                    .filenames
+                    .repeatable_prefix
+                    .or(self.filenames.repeatable_prefix),
+                separator: other.filenames.separator.or(self.filenames.separator),
+                suffixes: other.filenames.suffixes.or(self.filenames.suffixes),
+            },
+            version_formatting: other.version_formatting.or(self.version_formatting),
+            version_format: other.version_format.or(self.version_format),
+            version_pattern: other.version_pattern.or(self.version_pattern),
+            keyspace: other.keyspace.or(self.keyspace),
+            connection: PartialConnectionConfig {
+                nodes: other.connection.nodes.or(self.connection.nodes),
+                username: other.connection.username.or(self.connection.username),
+                password: other.connection.password.or(self.connection.password),
+                local_datacenter: other
+                    .connection
+                    .local_datacenter
+                    .or(self.connection.local_datacenter),
+                tls: PartialTlsConfig {
+                    enabled: other.connection.tls.enabled.or(self.connection.tls.enabled),
+                    ca_file: other.connection.tls.ca_file.or(self.connection.tls.ca_file),
+                    cert_file: other
+                        .connection
+                        .tls
+                        .cert_file
+                        .or(self.connection.tls.cert_file),
+                    key_file: other
+                        .connection
+                        .tls
+                        .key_file
+                        .or(self.connection.tls.key_file),
+                },
+            },
+            keyspace_definition: PartialKeyspaceDefinition {
+                class: other
+                    .keyspace_definition
+                    .class
+                    .or(self.keyspace_definition.class),
+                replication_factor: other
+                    .keyspace_definition
+                    .replication_factor
+                    .or(self.keyspace_definition.replication_factor),
+                datacenters: other
+                    .keyspace_definition
+                    .datacenters
+                    .or(self.keyspace_definition.datacenters),
+                durable_writes: other
+                    .keyspace_definition
+                    .durable_writes
+                    .or(self.keyspace_definition.durable_writes),
+            },
+            run_deadline_seconds: other.run_deadline_seconds.or(self.run_deadline_seconds),
+            migration_budget_seconds: other
+                .migration_budget_seconds
+                .or(self.migration_budget_seconds),
+            target: other.target.or(self.target),
+            executed_by: other.executed_by.or(self.executed_by),
+        }
+    }
+
+    pub fn locations(&self) -> Result<Vec<String>, ConfigError> {
+        match &self.locations {
+            None => Ok(vec!["migrations".to_string()]),
+            Some(locations) if locations.is_empty() => {
+                Err(invalid("locations", "expected at least one location"))
+            }
+            Some(locations) => Ok(locations.clone()),
+        }
+    }
+
+    pub fn ignore(&self) -> Result<Vec<String>, ConfigError> {
+        let ignore = self.ignore.clone().unwrap_or_default();
+        match ignore
+            .iter()
+            .find_map(|pattern| Pattern::new(pattern).err())
+        {
+            Some(err) => Err(invalid("ignore", &err.to_string())),
+            None => Ok(ignore),
+        }
+    }
+
+    pub fn unrecognized_files(&self) -> Result<UnrecognizedFiles, ConfigError> {
+        match self.unrecognized_files.as_deref() {
+            Some("ignore") => Ok(UnrecognizedFiles::Ignore),
+            Some("warn") => Ok(UnrecognizedFiles::Warn),
+            None | Some("error") => Ok(UnrecognizedFiles::Error),
+            Some(_) => Err(invalid(
+                "unrecognized_files",
+                "expected ignore, warn or error",
+            )),
+        }
+    }
+
+    pub fn version_formatting(&self) -> Result<VersionFormatting, ConfigError> {
+        match self.version_formatting.as_deref() {
+            None | Some("numeric") => Ok(VersionFormatting::Numeric),
+            Some("datetime") => Ok(VersionFormatting::Datetime),
+            Some("semver") => Ok(VersionFormatting::Semver),
+            Some("epoch") => Ok(VersionFormatting::Epoch),
+            Some("custom") => match (&self.version_format, &self.version_pattern) {
+                (Some(format), None) => {
+                    match StrftimeItems::new(format).any(|item| item == Item::Error) {
+                        true => Err(invalid("version_format", "invalid chrono format")),
+                        false => Ok(VersionFormatting::Custom(CustomVersionFormat::Datetime(
+                            format.clone(),
+                        ))),
+                    }
+                }
+                (None, Some(pattern)) => match Regex::new(pattern) {
+                    Err(err) => Err(invalid("version_pattern", &err.to_string())),
+                    Ok(regex) if regex.captures_len() < 2 => Err(invalid(
+                        "version_pattern",
+                        "expected at least one capture group",
+                    )),
+                    Ok(regex) => Ok(VersionFormatting::Custom(CustomVersionFormat::Pattern(
+                        VersionPattern::from(regex),
+                    ))),
+                },
+                _ => Err(invalid(
+                    "version_formatting",
+                    "expected either version_format or version_pattern for custom versions",
+                )),
+            },
+            Some(_) => Err(invalid(
+                "version_formatting",
+                "expected numeric, datetime, semver, epoch or custom",
+            )),
+        }
+    }
+
+    /// Validates the merged layers and fills in defaults.
+    pub fn build(self) -> Result<MigrationConfig, ConfigError> {
+        let keyspace = self
+            .keyspace
+            .clone()
+            .ok_or(MissingValueError("keyspace".to_string()))?;
+        if !KEYSPACE_NAME_REGEX.is_match(&keyspace) {
+            return Err(invalid(
+                "keyspace",
+                "expected up to 48 alphanumeric characters or underscores",
+            ));
+        }
+
+        Ok(MigrationConfig {
+            locations: self.locations()?,
+            ignore: self.ignore()?,
+            unrecognized_files: self.unrecognized_files()?,
+            filename_convention: self.filenames.build()?,
+            version_formatting: self.version_formatting()?,
+            keyspace,
+            connection: self.connection.build()?,
+            keyspace_definition: self.keyspace_definition.build()?,
+            run_deadline: seconds("run_deadline_seconds", self.run_deadline_seconds)?,
+            migration_budget: seconds("migration_budget_seconds", self.migration_budget_seconds)?,
+            target: self.target,
+            executed_by: self.executed_by,
+        })
+    }
+}
+
+impl PartialFilenameConvention {
+    pub fn build(&self) -> Result<FilenameConvention, ConfigError> {
+        let default = FilenameConvention::default();
+        let value = |key: &str, value: &Option<String>, default: String| match value {
+            Some(value) if value.is_empty() => Err(invalid(key, "must not be empty")),
+            Some(value) => Ok(value.clone()),
+            None => Ok(default),
+        };
+
+        let convention = FilenameConvention {
+            versioned_prefix: value(
+                "filenames.versioned_prefix",
+                &self.versioned_prefix,
+                default.versioned_prefix,
+            )?,
+            undo_prefix: value(
+                "filenames.undo_prefix",
+                &self.undo_prefix,
+                default.undo_prefix,
+            )?,
+            repeatable_prefix: value(
+                "filenames.repeatable_prefix",
+                &self.repeatable_prefix,
+                default.repeatable_prefix,
+            )?,
+            separator: value("filenames.separator", &self.separator, default.separator)?,
+            suffixes: self.suffixes.clone().unwrap_or(default.suffixes),
+        };
+
+        let prefixes = [
+            &convention.versioned_prefix,
+            &convention.undo_prefix,
+            &convention.repeatable_prefix,
+        ];
+        if prefixes.iter().collect::<HashSet<_>>().len() < prefixes.len() {
+            return Err(invalid("filenames", "expected distinct prefixes"));
+        }
+        if convention.suffixes.is_empty() || convention.suffixes.iter().any(String::is_empty) {
+            return Err(invalid(
+                "filenames.suffixes",
+                "expected at least one non-empty suffix",
+            ));
+        }
+
+        Ok(convention)
+    }
+}
+
+impl PartialConnectionConfig {
+    fn build(self) -> Result<ConnectionConfig, ConfigError> {
+        let nodes = self
+            .nodes
+            .unwrap_or_else(|| ConnectionConfig::default().nodes);
+        if nodes.is_empty() {
+            return Err(invalid("connection.nodes", "expected at least one node"));
+        }
+
+        match (&self.username, &self.password) {
+            (Some(_), None) => return Err(MissingValueError("connection.password".to_string())),
+            (None, Some(_)) => return Err(MissingValueError("connection.username".to_string())),
+            _ => {}
+        }
+        if self
+            .local_datacenter
+            .as_ref()
+            .is_some_and(|datacenter| datacenter.trim().is_empty())
+        {
+            return Err(invalid("connection.local_datacenter", "must not be empty"));
+        }
+
+        Ok(ConnectionConfig {
+            nodes,
+            username: self.username,
+            password: self.password,
+            local_datacenter: self.local_datacenter,
+            tls: self.tls.build()?,
+        })
+    }
+}
+
+impl PartialTlsConfig {
+    fn build(self) -> Result<Option<TlsConfig>, ConfigError> {
+        let has_files =
+            self.ca_file.is_some() || self.cert_file.is_some() || self.key_file.is_some();
+        if !self.enabled.unwrap_or(has_files) {
+            return Ok(None);
+        }
+
+        match (&self.cert_file, &self.key_file) {
+            (Some(_), None) => Err(MissingValueError("connection.tls.key_file".to_string())),
+            (None, Some(_)) => Err(MissingValueError("connection.tls.cert_file".to_string())),
+            _ => Ok(Some(TlsConfig {
+                ca_file: self.ca_file,
+                cert_file: self.cert_file,
+                key_file: self.key_file,
+            })),
+        }
+    }
+}
+
+impl PartialKeyspaceDefinition {
+    fn build(self) -> Result<Option<KeyspaceDefinition>, ConfigError> {
+        let class = match (&self.class, &self.replication_factor, &self.datacenters) {
+            (None, None, None) => return Ok(None),
+            (Some(class), _, _) => class.as_str(),
+            (None, _, Some(_)) => "NetworkTopologyStrategy",
+            (None, _, None) => "SimpleStrategy",
+        };
+
+        let replication = match class {
+            "SimpleStrategy" => {
+                let replication_factor = self.replication_factor.ok_or(MissingValueError(
+                    "keyspace_definition.replication_factor".to_string(),
+                ))?;
+                if replication_factor == 0 {
+                    return Err(invalid(
+                        "keyspace_definition.replication_factor",
+                        "must be greater than zero",
+                    ));
+                }
+                Replication::SimpleStrategy { replication_factor }
+            }
+            "NetworkTopologyStrategy" => {
+                let datacenters = self
+                    .datacenters
+                    .filter(|datacenters| !datacenters.is_empty())
+                    .ok_or(MissingValueError(
+                        "keyspace_definition.datacenters".to_string(),
+                    ))?;
+                if datacenters
+                    .values()
+                    .any(|replication_factor| *replication_factor == 0)
+                {
+                    return Err(invalid(
+                        "keyspace_definition.datacenters",
+                        "replication factors must be greater than zero",
+                    ));
+                }
+                Replication::NetworkTopologyStrategy { datacenters }
+            }
+            _ => {
+                return Err(invalid(
+                    "keyspace_definition.class",
+                    "expected SimpleStrategy or NetworkTopologyStrategy",
+                ))
+            }
+        };
+
+        Ok(Some(KeyspaceDefinition {
+            replication,
+            durable_writes: self.durable_writes.unwrap_or(true),
+        }))
+    }
+}
+
+fn parse_env<T>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    key: &str,
+) -> Result<Option<T>, ConfigError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    lookup(key)
+        .map(|value| parse_value(key, &value))
+        .transpose()
+}
+
+fn parse_value<T>(key: &str, value: &str) -> Result<T, ConfigError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|err: T::Err| invalid(key, &err.to_string()))
+}
+
+fn seconds(key: &str, seconds: Option<u64>) -> Result<Option<Duration>, ConfigError> {
+    match seconds {
+        Some(0) => Err(invalid(key, "must be greater than zero")),
+        seconds => Ok(seconds.map(Duration::from_secs)),
+    }
+}
+
+fn invalid(key: &str, message: &str) -> ConfigError {
+    InvalidValueError {
+        key: key.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::collections::HashMap;
+
+    const CONFIG: &str = r#"
+        keyspace = "app"
+        locations = ["db/migrations", "db/shared"]
+
+        [connection]
+        nodes = ["10.0.0.1:9042"]
+
+        [keyspace_definition]
+        replication_factor = 1
+
+        [profiles.prod]
+        keyspace = "app_prod"
+        run_deadline_seconds = 600
+
+        [profiles.prod.keyspace_definition]
+        datacenters = { eu = 3 }
+    "#;
+
+    #[test]
+    fn test_load_profile() {
+        // when
+        let config = PartialMigrationConfig::from_toml("flowfine.toml", CONFIG, Some("prod"))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // then
+        assert_eq!(config.keyspace, "app_prod");
+        assert_eq!(config.locations, vec!["db/migrations", "db/shared"]);
+        assert_eq!(config.connection.nodes, vec!["10.0.0.1:9042"]);
+        assert_eq!(config.run_deadline, Some(Duration::from_secs(600)));
+        assert_eq!(
+            config.keyspace_definition.unwrap().replication,
+            Replication::NetworkTopologyStrategy {
+                datacenters: BTreeMap::from([("eu".to_string(), 3)])
+            }
+        );
+    }
+
+    #[test]
+    fn test_override_with_env_vars() {
+        // given
+        let env_vars = HashMap::from([
+            ("FLOWFINE_KEYSPACE", "app_dev"),
+            ("FLOWFINE_NODES", "10.0.0.2:9042, 10.0.0.3:9042"),
+            ("FLOWFINE_MIGRATION_BUDGET_SECONDS", "30"),
+            ("FLOWFINE_IGNORE", "*.md, drafts/*"),
+            ("FLOWFINE_UNRECOGNIZED_FILES", "warn"),
+        ]);
+        let base = PartialMigrationConfig::from_toml("flowfine.toml", CONFIG, None).unwrap();
+
+        // when
+        let overrides =
+            PartialMigrationConfig::from_env_vars(|key| env_vars.get(key).map(|v| v.to_string()))
+                .unwrap();
+        let config = base.merge(overrides).build().unwrap();
+
+        // then
+        assert_eq!(config.keyspace, "app_dev");
+        assert_eq!(
+            config.connection.nodes,
+            vec!["10.0.0.2:9042", "10.0.0.3:9042"]
+        );
+        assert_eq!(config.migration_budget, Some(Duration::from_secs(30)));
+        assert_eq!(config.ignore, vec!["*.md", "drafts/*"]);
+        assert_eq!(config.unrecognized_files, UnrecognizedFiles::Warn);
+    }
+
+    #[rstest(
+        content,
+        expected_key,
+        case("keyspace = \"app-1\"", "keyspace"),
+        case(
+            "keyspace = \"app\"\nversion_formatting = \"roman\"",
+            "version_formatting"
+        ),
+        case(
+            "keyspace = \"app\"\nversion_formatting = \"custom\"",
+            "version_formatting"
+        ),
+        case(
+            "keyspace = \"app\"\nversion_formatting = \"custom\"\nversion_format = \"%Y%\"",
+            "version_format"
+        ),
+        case(
+            "keyspace = \"app\"\nversion_formatting = \"custom\"\nversion_pattern = \"\\\\d+\"",
+            "version_pattern"
+        ),
+        case(
+            "keyspace = \"app\"\n[keyspace_definition]\nclass = \"LocalStrategy\"",
+            "keyspace_definition.class"
+        ),
+        case(
+            "keyspace = \"app\"\n[keyspace_definition]\nclass = \"NetworkTopologyStrategy\"",
+            "keyspace_definition.datacenters"
+        ),
+        case(
+            "keyspace = \"app\"\n[connection]\nusername = \"flowfine\"",
+            "connection.password"
+        ),
+        case(
+            "keyspace = \"app\"\n[connection.tls]\ncert_file = \"client.pem\"",
+            "connection.tls.key_file"
+        ),
+        case(
+            "keyspace = \"app\"\n[connection]\nlocal_datacenter = \" \"",
+            "connection.local_datacenter"
+        ),
+        case("keyspace = \"app\"\nrun_deadline_seconds = 0", "run_deadline_seconds"),
+        case("keyspace = \"app\"\nlocations = []", "locations"),
+        case("keyspace = \"app\"\nignore = [\"[\"]", "ignore"),
+        case(
+            "keyspace = \"app\"\nunrecognized_files = \"skip\"",
+            "unrecognized_files"
+        ),
+        case(
+            "keyspace = \"app\"\n[filenames]\nseparator = \"\"",
+            "filenames.separator"
+        ),
+        case("keyspace = \"app\"\n[filenames]\nundo_prefix = \"V\"", "filenames"),
+        case("keyspace = \"app\"\n[filenames]\nsuffixes = []", "filenames.suffixes"),
+        case("locations = [\"migrations\"]", "keyspace")
+    )]
+    fn test_report_offending_key(content: &str, expected_key: &str) {
+        // when
+        let result = PartialMigrationConfig::from_toml("flowfine.toml", content, None)
+            .unwrap()
+            .build();
+
+        // then
+        match result {
+            Err(InvalidValueError { key, .. }) | Err(MissingValueError(key)) => {
+                assert_eq!(key, expected_key)
+            }
+            _ => panic!("expected an error for {}", expected_key),
+        }
+    }
+
+    #[rstest(content, expected_result,
+    case("version_formatting = \"semver\"", VersionFormatting::Semver),
+    case("version_formatting = \"epoch\"", VersionFormatting::Epoch),
+    case(
+        "version_formatting = \"custom\"\nversion_format = \"%Y_%m_%d\"",
+        VersionFormatting::Custom(CustomVersionFormat::Datetime("%Y_%m_%d".to_string()))
+    ),
+    case(
+        "version_formatting = \"custom\"\nversion_pattern = \"(\\\\d+)-(\\\\d+)\"",
+        VersionFormatting::Custom(CustomVersionFormat::Pattern(
+            VersionPattern::new("(\\d+)-(\\d+)").unwrap()
+        ))
+    ),
+    )]
+    fn test_load_version_formatting(content: &str, expected_result: VersionFormatting) {
+        // when
+        let version_formatting = PartialMigrationConfig::from_toml("flowfine.toml", content, None)
+            .unwrap()
+            .version_formatting();
+
+        // then
+        assert_eq!(version_formatting.unwrap(), expected_result);
+    }
+
+    #[test]
+    fn test_load_secured_connection() {
+        // given
+        let content = r#"
+            keyspace = "app"
+
+            [connection]
+            username = "flowfine"
+            password = "secret"
+            local_datacenter = "eu"
+
+            [connection.tls]
+            ca_file = "ca.pem"
+        "#;
+        let env_vars = HashMap::from([
+            ("FLOWFINE_TLS_CERT_FILE", "client.pem"),
+            ("FLOWFINE_TLS_KEY_FILE", "client.key"),
+        ]);
+
+        // when
+        let config = PartialMigrationConfig::from_toml("flowfine.toml", content, None)
+            .unwrap()
+            .merge(
+                PartialMigrationConfig::from_env_vars(|key| {
+                    env_vars.get(key).map(|v| v.to_string())
+                })
+                .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        // then
+        assert_eq!(config.connection.local_datacenter.as_deref(), Some("eu"));
+        assert_eq!(
+            config.connection.tls,
+            Some(TlsConfig {
+                ca_file: Some(PathBuf::from("ca.pem")),
+                cert_file: Some(PathBuf::from("client.pem")),
+                key_file: Some(PathBuf::from("client.key")),
+            })
+        );
+    }
+
+    #[test]
+    fn test_reject_unknown_key() {
+        // when
+        let result = PartialMigrationConfig::from_toml("flowfine.toml", "keyspac = \"app\"", None);
+
+        // then
+        assert!(matches!(&result, Err(ParseError(..))));
+        assert!(result.unwrap_err().to_string().contains("keyspac"));
+    }
+
+    #[test]
+    fn test_reject_invalid_env_var() {
+        // when
+        let result = PartialMigrationConfig::from_env_vars(|key| {
+            (key == "FLOWFINE_REPLICATION_FACTOR").then(|| "three".to_string())
+        });
+
+        // then
+        assert!(matches!(
+            result,
+            Err(InvalidValueError { key, .. }) if key == "FLOWFINE_REPLICATION_FACTOR"
+        ));
+    }
+
+    #[test]
+    fn test_reject_unknown_profile() {
+        // when
+        let result = PartialMigrationConfig::from_toml("flowfine.toml", CONFIG, Some("staging"));
+
+        // then
+        assert!(matches!(result, Err(UnknownProfileError(profile)) if profile == "staging"));
+    }
+}
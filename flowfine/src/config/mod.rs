@@ -0,0 +1,366 @@
+use crate::migration::MigrationKind;
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub mod loader;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MigrationConfig {
+    /// Directories scanned recursively for migrations, versions being unique across all of them.
+    pub locations: Vec<String>,
+    /// Glob patterns of files to skip, matched against the path relative to a location
+    /// or, without a `/`, against the file name. Added to those of `.flowfineignore` files.
+    pub ignore: Vec<String>,
+    pub unrecognized_files: UnrecognizedFiles,
+    pub filename_convention: FilenameConvention,
+    pub version_formatting: VersionFormatting,
+    pub keyspace: String,
+    pub connection: ConnectionConfig,
+    /// Creates the keyspace with this definition when it does not exist yet.
+    pub keyspace_definition: Option<KeyspaceDefinition>,
+    /// Overall time a run may take, measured from its start.
+    pub run_deadline: Option<Duration>,
+    /// Time a single migration may take before the run stops.
+    pub migration_budget: Option<Duration>,
+    /// Version to migrate to; applied versions above it are undone.
+    pub target: Option<String>,
+    /// Identity recorded in history instead of `user@hostname`.
+    pub executed_by: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionConfig {
+    pub nodes: Vec<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Routes requests to nodes of this datacenter, e.g. `eu-west`.
+    pub local_datacenter: Option<String>,
+    pub tls: Option<TlsConfig>,
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        Self {
+            nodes: vec!["127.0.0.1:9042".to_string()],
+            username: None,
+            password: None,
+            local_datacenter: None,
+            tls: None,
+        }
+    }
+}
+
+/// Encrypts connections, verifying the cluster against `ca_file` or the system certificates.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub ca_file: Option<PathBuf>,
+    /// Client certificate presented to the cluster, requires `key_file`.
+    pub cert_file: Option<PathBuf>,
+    pub key_file: Option<PathBuf>,
+}
+
+const RUST_SUFFIX: &str = ".rs";
+
+/// Handling of files found in migration locations without one of the
+/// `FilenameConvention::suffixes`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnrecognizedFiles {
+    Ignore,
+    Warn,
+    #[default]
+    Error,
+}
+
+/// Shape of migration filenames, `<prefix><version><separator><description><suffix>`,
+/// repeatable migrations having no version, e.g. `V1__create_users.cql` or `R__views.cql`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FilenameConvention {
+    pub versioned_prefix: String,
+    pub undo_prefix: String,
+    pub repeatable_prefix: String,
+    pub separator: String,
+    /// Accepted extensions, e.g. `.cql` or `.cql.tmpl`; new migrations get the first one.
+    pub suffixes: Vec<String>,
+}
+
+impl Default for FilenameConvention {
+    fn default() -> Self {
+        Self {
+            versioned_prefix: "V".to_string(),
+            undo_prefix: "U".to_string(),
+            repeatable_prefix: "R".to_string(),
+            separator: "__".to_string(),
+            suffixes: vec![".cql".to_string()],
+        }
+    }
+}
+
+impl FilenameConvention {
+    pub fn prefix(&self, kind: MigrationKind) -> &str {
+        match kind {
+            MigrationKind::Versioned => &self.versioned_prefix,
+            MigrationKind::Undo => &self.undo_prefix,
+            MigrationKind::Repeatable => &self.repeatable_prefix,
+        }
+    }
+
+    /// Kind of migration named by `filename`, the longest matching prefix winning.
+    pub fn kind(&self, filename: &str) -> Option<MigrationKind> {
+        [
+            MigrationKind::Versioned,
+            MigrationKind::Undo,
+            MigrationKind::Repeatable,
+        ]
+        .into_iter()
+        .filter(|kind| filename.starts_with(self.prefix(*kind)))
+        .max_by_key(|kind| self.prefix(*kind).len())
+    }
+
+    /// Longest of the accepted suffixes `filename` ends with.
+    pub fn suffix(&self, filename: &str) -> Option<&str> {
+        self.suffixes
+            .iter()
+            .filter(|suffix| filename.ends_with(suffix.as_str()))
+            .max_by_key(|suffix| suffix.len())
+            .map(String::as_str)
+    }
+
+    /// Describes the filenames expected for `kind`, e.g. `V<version>__<description>.cql`.
+    pub fn pattern(&self, kind: MigrationKind) -> String {
+        let version = match kind {
+            MigrationKind::Repeatable => "",
+            _ => "<version>",
+        };
+
+        format!(
+            "{}{}{}<description>{}",
+            self.prefix(kind),
+            version,
+            self.separator,
+            self.suffix_pattern()
+        )
+    }
+
+    /// Describes the filenames of every kind of migration.
+    pub fn patterns(&self) -> String {
+        [
+            MigrationKind::Versioned,
+            MigrationKind::Undo,
+            MigrationKind::Repeatable,
+        ]
+        .map(|kind| self.pattern(kind))
+        .join(" or ")
+    }
+
+    /// Describes the filenames of the callback scripts run on `event`, e.g. `afterMigrate.cql`
+    /// or `afterMigrate__<description>.cql`.
+    pub fn callback_pattern(&self, event: &str) -> String {
+        format!(
+            "{event}[{}<description>]{}",
+            self.separator,
+            self.suffix_pattern()
+        )
+    }
+
+    /// Names a new versioned migration, replacing spaces of `description` with underscores.
+    pub fn versioned_filename(&self, version: &str, description: &str) -> String {
+        let suffix = self
+            .suffixes
+            .first()
+            .map(String::as_str)
+            .unwrap_or_default();
+        self.filename(version, description, suffix)
+    }
+
+    /// Name under which a `RustMigration` is planned and recorded in history.
+    pub fn rust_filename(&self, version: &str, description: &str) -> String {
+        self.filename(version, description, RUST_SUFFIX)
+    }
+
+    /// Whether `filename` was given by `rust_filename`.
+    pub fn is_rust_filename(&self, filename: &str) -> bool {
+        filename.ends_with(RUST_SUFFIX)
+    }
+
+    fn filename(&self, version: &str, description: &str, suffix: &str) -> String {
+        format!(
+            "{}{}{}{}{}",
+            self.versioned_prefix,
+            version,
+            self.separator,
+            description.replace(' ', "_"),
+            suffix
+        )
+    }
+
+    fn suffix_pattern(&self) -> String {
+        match self.suffixes.as_slice() {
+            [suffix] => suffix.clone(),
+            suffixes => format!("{{{}}}", suffixes.join(",")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VersionFormatting {
+    Numeric,
+    Datetime,
+    /// Semantic versions, e.g. `1.4.0` or `2.0.0-rc.1`; build metadata is not accepted as it
+    /// does not take part in ordering.
+    Semver,
+    /// Seconds since the Unix epoch, e.g. `1693742400`.
+    Epoch,
+    Custom(CustomVersionFormat),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CustomVersionFormat {
+    /// chrono format string, e.g. `%Y_%m_%d_%H%M`, dates without time being midnight.
+    Datetime(String),
+    /// Regex matching the whole version, its capture groups compared in order, numerically
+    /// when they are digits, e.g. `(\d+)-(alpha|beta|rc)-(\d+)`.
+    Pattern(VersionPattern),
+}
+
+/// Regex of a `CustomVersionFormat::Pattern`, compiled once and compared by its source.
+#[derive(Clone, Debug)]
+pub struct VersionPattern(Regex);
+
+impl VersionPattern {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Regex::new(pattern).map(Self)
+    }
+
+    pub fn regex(&self) -> &Regex {
+        &self.0
+    }
+}
+
+impl From<Regex> for VersionPattern {
+    fn from(regex: Regex) -> Self {
+        Self(regex)
+    }
+}
+
+impl PartialEq for VersionPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for VersionPattern {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyspaceDefinition {
+    pub replication: Replication,
+    pub durable_writes: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Replication {
+    SimpleStrategy { replication_factor: u32 },
+    NetworkTopologyStrategy { datacenters: BTreeMap<String, u32> },
+}
+
+impl KeyspaceDefinition {
+    pub fn to_cql(&self, keyspace: &str) -> String {
+        format!(
+            "CREATE KEYSPACE IF NOT EXISTS {} WITH replication = {} AND durable_writes = {};",
+            keyspace,
+            self.replication.to_cql(),
+            self.durable_writes
+        )
+    }
+}
+
+impl Replication {
+    pub fn to_cql(&self) -> String {
+        let options = self
+            .options()
+            .into_iter()
+            .map(|(key, value)| format!("'{}': '{}'", key, value))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!("{{{}}}", options)
+    }
+
+    /// Compares against the replication map stored in `system_schema.keyspaces`,
+    /// where the class is usually fully qualified, e.g. `org.apache.cassandra.locator.SimpleStrategy`.
+    pub fn matches(&self, live_replication: &HashMap<String, String>) -> bool {
+        let expected = self.options();
+
+        expected.len() == live_replication.len()
+            && expected.iter().all(|(key, value)| {
+                live_replication
+                    .get(key)
+                    .is_some_and(|live_value| match key.as_str() {
+                        "class" => live_value.rsplit('.').next() == Some(value.as_str()),
+                        _ => live_value == value,
+                    })
+            })
+    }
+
+    fn options(&self) -> BTreeMap<String, String> {
+        let mut options = BTreeMap::new();
+
+        match self {
+            Replication::SimpleStrategy { replication_factor } => {
+                options.insert("class".to_string(), "SimpleStrategy".to_string());
+                options.insert(
+                    "replication_factor".to_string(),
+                    replication_factor.to_string(),
+                );
+            }
+            Replication::NetworkTopologyStrategy { datacenters } => {
+                options.insert("class".to_string(), "NetworkTopologyStrategy".to_string());
+                for (datacenter, replication_factor) in datacenters {
+                    options.insert(datacenter.clone(), replication_factor.to_string());
+                }
+            }
+        }
+
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(replication, expected_result,
+    case(Replication::SimpleStrategy { replication_factor: 3 }, "{'class': 'SimpleStrategy', 'replication_factor': '3'}"),
+    case(Replication::NetworkTopologyStrategy { datacenters: BTreeMap::from([("dc1".to_string(), 3), ("dc2".to_string(), 2)]) },
+        "{'class': 'NetworkTopologyStrategy', 'dc1': '3', 'dc2': '2'}"),
+    )]
+    fn test_replication_to_cql(replication: Replication, expected_result: &str) {
+        assert_eq!(replication.to_cql(), expected_result);
+    }
+
+    #[rstest(live_replication, expected_result,
+    case(vec![("class", "org.apache.cassandra.locator.NetworkTopologyStrategy"), ("dc1", "3")], true),
+    case(vec![("class", "NetworkTopologyStrategy"), ("dc1", "3")], true),
+    case(vec![("class", "org.apache.cassandra.locator.NetworkTopologyStrategy"), ("dc1", "1")], false),
+    case(vec![("class", "org.apache.cassandra.locator.NetworkTopologyStrategy"), ("dc1", "3"), ("dc2", "3")], false),
+    case(vec![("class", "org.apache.cassandra.locator.SimpleStrategy"), ("replication_factor", "3")], false),
+    )]
+    fn test_replication_matches(live_replication: Vec<(&str, &str)>, expected_result: bool) {
+        // given
+        let replication = Replication::NetworkTopologyStrategy {
+            datacenters: BTreeMap::from([("dc1".to_string(), 3)]),
+        };
+        let live_replication = live_replication
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        // when
+        let matches = replication.matches(&live_replication);
+
+        // then
+        assert_eq!(matches, expected_result);
+    }
+}
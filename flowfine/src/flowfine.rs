@@ -0,0 +1,260 @@
+use crate::config::{FilenameConvention, MigrationConfig, UnrecognizedFiles, VersionFormatting};
+use crate::migration::parser::parse_with_rust_migrations;
+use crate::migration::rebase::{plan_rebase, RebaseError, RebasePlan};
+use crate::migration::rust::RustMigration;
+use crate::migration::source::{DirectorySource, MigrationFile, MigrationSource};
+use crate::migration::{CallbackScript, FileError, Migration, MigrationParsingError};
+use crate::runner::callback::{MigrationCallback, SqlCallbacks};
+use crate::runner::event::{EventSink, MigrationEvent, StdoutSink};
+use crate::runner::plan::MigrationPlan;
+use crate::runner::{
+    AppliedMigration, MigrationExecutionError, MigrationRunner, ScyllaMigrationRunner,
+};
+use crate::session::{build_session, SessionError};
+use scylla::Session;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::OnceCell;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Error)]
+pub enum FlowfineError {
+    #[error("Migrations could not be loaded: {0}")]
+    LoadError(#[from] FileError),
+
+    #[error("Migrations could not be parsed:\n{}", format_parsing_errors(.0))]
+    ParseError(Vec<MigrationParsingError>),
+
+    #[error(transparent)]
+    SessionError(#[from] SessionError),
+
+    #[error(transparent)]
+    ExecutionError(#[from] MigrationExecutionError),
+
+    #[error(transparent)]
+    RebaseError(#[from] RebaseError),
+}
+
+/// Runs the migrations of `MigrationConfig::locations` against `MigrationConfig::keyspace`,
+/// connecting with `MigrationConfig::connection` unless a session is given.
+pub struct Flowfine {
+    config: MigrationConfig,
+    session: OnceCell<Arc<Session>>,
+    callbacks: Vec<Arc<dyn MigrationCallback>>,
+    event_sink: Option<Arc<dyn EventSink>>,
+    source: Option<Arc<dyn MigrationSource>>,
+    rust_migrations: Vec<Arc<dyn RustMigration>>,
+}
+
+impl Flowfine {
+    pub fn new(config: MigrationConfig) -> Self {
+        Self {
+            config,
+            session: OnceCell::new(),
+            callbacks: Vec::new(),
+            event_sink: None,
+            source: None,
+            rust_migrations: Vec::new(),
+        }
+    }
+
+    /// Reuses an existing session instead of connecting on first use.
+    pub fn with_session(mut self, session: Arc<Session>) -> Self {
+        self.session = OnceCell::new_with(Some(session));
+        self
+    }
+
+    /// Registers a callback next to the SQL callbacks found in the migration locations.
+    pub fn with_callback(mut self, callback: Arc<dyn MigrationCallback>) -> Self {
+        self.callbacks.push(callback);
+        self
+    }
+
+    pub fn with_event_sink(mut self, event_sink: Arc<dyn EventSink>) -> Self {
+        self.event_sink = Some(event_sink);
+        self
+    }
+
+    /// Reads migrations from `source` instead of `MigrationConfig::locations`, e.g. from
+    /// `EmbeddedMigrations`, parsing them with the version formatting, filename convention and
+    /// strictness of the source when it has its own.
+    pub fn with_source(mut self, source: Arc<dyn MigrationSource>) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Registers a migration written in Rust, ordered by version with the migration files.
+    pub fn with_rust_migration(mut self, rust_migration: Arc<dyn RustMigration>) -> Self {
+        self.rust_migrations.push(rust_migration);
+        self
+    }
+
+    pub fn config(&self) -> &MigrationConfig {
+        &self.config
+    }
+
+    /// Loads and parses the migration files, reporting every unparsable file at once.
+    pub fn migrations(&self) -> Result<Vec<Migration>, FlowfineError> {
+        Ok(self.load()?.0)
+    }
+
+    pub async fn migrate(&self) -> Result<Vec<AppliedMigration>, FlowfineError> {
+        self.migrate_with_cancellation(CancellationToken::new())
+            .await
+    }
+
+    pub async fn migrate_with_cancellation(
+        &self,
+        cancellation: CancellationToken,
+    ) -> Result<Vec<AppliedMigration>, FlowfineError> {
+        let (migrations, scripts) = self.load()?;
+        let runner = self.runner(scripts).await?;
+
+        Ok(runner
+            .run_with_cancellation(migrations, cancellation)
+            .await?)
+    }
+
+    /// Plans the run without executing it, listing every migration with its action.
+    pub async fn info(&self) -> Result<MigrationPlan, FlowfineError> {
+        let (migrations, scripts) = self.load()?;
+        Ok(self.runner(scripts).await?.plan(&migrations).await?)
+    }
+
+    pub async fn validate(&self) -> Result<(), FlowfineError> {
+        let (migrations, scripts) = self.load()?;
+        Ok(self.runner(scripts).await?.validate(&migrations).await?)
+    }
+
+    pub async fn baseline(
+        &self,
+        version: &str,
+        description: &str,
+    ) -> Result<AppliedMigration, FlowfineError> {
+        Ok(self
+            .runner(Vec::new())
+            .await?
+            .baseline(version, description)
+            .await?)
+    }
+
+    pub async fn repair(&self) -> Result<Vec<String>, FlowfineError> {
+        let (migrations, scripts) = self.load()?;
+        Ok(self.runner(scripts).await?.repair(&migrations).await?)
+    }
+
+    pub async fn clean(&self) -> Result<(), FlowfineError> {
+        Ok(self.runner(Vec::new()).await?.clean().await?)
+    }
+
+    /// Proposes new versions for unapplied migrations colliding with applied or other local
+    /// versions, without renaming anything until `RebasePlan::apply`.
+    pub async fn rebase(&self) -> Result<RebasePlan, FlowfineError> {
+        let files = self.files()?;
+        let applied_migrations = self.runner(Vec::new()).await?.history().await?;
+
+        Ok(plan_rebase(
+            &files,
+            &applied_migrations,
+            &self.rust_migrations,
+            &self.version_formatting(),
+            &self.filename_convention(),
+        )?)
+    }
+
+    fn version_formatting(&self) -> VersionFormatting {
+        self.source
+            .as_ref()
+            .and_then(|source| source.version_formatting())
+            .unwrap_or_else(|| self.config.version_formatting.clone())
+    }
+
+    fn filename_convention(&self) -> FilenameConvention {
+        self.source
+            .as_ref()
+            .and_then(|source| source.filename_convention())
+            .unwrap_or_else(|| self.config.filename_convention.clone())
+    }
+
+    fn unrecognized_files(&self) -> UnrecognizedFiles {
+        self.source
+            .as_ref()
+            .and_then(|source| source.unrecognized_files())
+            .unwrap_or(self.config.unrecognized_files)
+    }
+
+    fn files(&self) -> Result<Vec<MigrationFile>, FileError> {
+        match &self.source {
+            Some(source) => source.files(),
+            None => self
+                .config
+                .locations
+                .iter()
+                .map(|location| {
+                    DirectorySource::new(location)
+                        .with_ignore_patterns(&self.config.ignore)
+                        .with_filename_convention(&self.config.filename_convention)
+                })
+                .collect::<Vec<_>>()
+                .files(),
+        }
+    }
+
+    fn load(&self) -> Result<(Vec<Migration>, Vec<CallbackScript>), FlowfineError> {
+        let files = self.files()?;
+        let result = parse_with_rust_migrations(
+            &files,
+            &self.rust_migrations,
+            &self.version_formatting(),
+            &self.filename_convention(),
+            self.unrecognized_files(),
+        );
+        if !result.unrecognized_files().is_empty() {
+            let event_sink = self.event_sink.clone().unwrap_or(Arc::new(StdoutSink));
+            for path in result.unrecognized_files() {
+                event_sink.emit(&MigrationEvent::UnrecognizedFile { path: path.clone() });
+            }
+        }
+        let scripts = result.callbacks().to_vec();
+        let migrations = result.into_result().map_err(FlowfineError::ParseError)?;
+
+        Ok((migrations, scripts))
+    }
+
+    async fn runner(
+        &self,
+        scripts: Vec<CallbackScript>,
+    ) -> Result<ScyllaMigrationRunner, FlowfineError> {
+        let session = self
+            .session
+            .get_or_try_init(|| async {
+                build_session(&self.config.connection).await.map(Arc::new)
+            })
+            .await?;
+        let mut runner = ScyllaMigrationRunner::from_config(session.clone(), &self.config)
+            .with_version_formatting(self.version_formatting())
+            .with_filename_convention(self.filename_convention());
+        if !scripts.is_empty() {
+            runner = runner.with_callback(Arc::new(SqlCallbacks::new(scripts)));
+        }
+        for callback in &self.callbacks {
+            runner = runner.with_callback(callback.clone());
+        }
+        for rust_migration in &self.rust_migrations {
+            runner = runner.with_rust_migration(rust_migration.clone());
+        }
+        if let Some(event_sink) = &self.event_sink {
+            runner = runner.with_event_sink(event_sink.clone());
+        }
+
+        Ok(runner)
+    }
+}
+
+fn format_parsing_errors(errors: &[MigrationParsingError]) -> String {
+    errors
+        .iter()
+        .map(|err| format!("  {}", err))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
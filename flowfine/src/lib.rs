@@ -1,3 +1,10 @@
 pub mod config;
+mod flowfine;
 pub mod migration;
 pub mod runner;
+pub mod session;
+
+pub use crate::flowfine::{Flowfine, FlowfineError};
+
+#[cfg(feature = "test-support")]
+pub mod testing;
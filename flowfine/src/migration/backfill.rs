@@ -0,0 +1,304 @@
+use crate::migration::backfill::BackfillError::*;
+use crate::migration::rust::RustMigrationError;
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use lazy_static::lazy_static;
+use scylla::frame::response::result::Row;
+use scylla::prepared_statement::PreparedStatement;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+use thiserror::Error;
+
+lazy_static! {
+    static ref CHECKPOINT_TABLE_NAME: String = "flowfine_backfill".to_string();
+}
+
+#[derive(Debug, Error)]
+pub enum BackfillError {
+    #[error("Backfill checkpoints could not be accessed: {0}")]
+    CheckpointError(QueryError),
+
+    #[error("Backfill query could not be prepared: {0}")]
+    PrepareError(QueryError),
+
+    #[error("Token range {0} could not be read: {1}")]
+    ScanError(TokenRange, QueryError),
+
+    #[error("Row of token range {0} could not be transformed: {1}")]
+    TransformError(TokenRange, RustMigrationError),
+}
+
+/// Applied by a `Backfill` to every row it reads, e.g. to write a re-encoded copy of it.
+#[async_trait]
+pub trait RowTransform: Send + Sync {
+    async fn transform(&self, session: &Session, row: Row) -> Result<(), RustMigrationError>;
+}
+
+/// Murmur3 tokens greater than `start` and up to `end`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TokenRange {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Display for TokenRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {}]", self.start, self.end)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BackfillSummary {
+    pub ranges: usize,
+    /// Ranges completed by an earlier, interrupted run and not read again.
+    pub resumed_ranges: usize,
+    pub rows: u64,
+}
+
+/// Iterates a table by token range, a few ranges at a time and page by page, so a
+/// `RustMigration` can transform hundreds of millions of rows without timing out.
+///
+/// Completed ranges are checkpointed under `name` in `<keyspace>.flowfine_backfill`, so running
+/// the same backfill again after an interruption resumes with the ranges left. The range count
+/// must stay the same between runs for checkpoints to match.
+#[derive(Clone, Debug)]
+pub struct Backfill {
+    name: String,
+    keyspace: String,
+    table: String,
+    partition_key: Vec<String>,
+    columns: Vec<String>,
+    range_count: usize,
+    parallelism: usize,
+    page_size: i32,
+}
+
+impl Backfill {
+    pub fn new(name: &str, keyspace: &str, table: &str, partition_key: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            keyspace: keyspace.to_string(),
+            table: table.to_string(),
+            partition_key: partition_key
+                .iter()
+                .map(|column| column.to_string())
+                .collect(),
+            columns: Vec::new(),
+            range_count: 256,
+            parallelism: 4,
+            page_size: 1000,
+        }
+    }
+
+    /// Selects these columns instead of all of them.
+    pub fn with_columns(mut self, columns: &[&str]) -> Self {
+        self.columns = columns.iter().map(|column| column.to_string()).collect();
+        self
+    }
+
+    /// Number of equal token ranges the ring is split into, 256 by default.
+    pub fn with_range_count(mut self, range_count: usize) -> Self {
+        self.range_count = range_count.max(1);
+        self
+    }
+
+    /// Number of ranges read concurrently, 4 by default.
+    pub fn with_parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Rows fetched per page, 1000 by default.
+    pub fn with_page_size(mut self, page_size: i32) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    pub async fn run(
+        &self,
+        session: &Session,
+        transform: &dyn RowTransform,
+    ) -> Result<BackfillSummary, BackfillError> {
+        self.create_checkpoint_table(session).await?;
+        let completed_ranges = self.find_completed_ranges(session).await?;
+        let ranges = split_token_ranges(self.range_count);
+        let pending_ranges = ranges
+            .iter()
+            .filter(|range| !completed_ranges.contains(range))
+            .copied()
+            .collect::<Vec<_>>();
+
+        let mut select = session
+            .prepare(self.select_statement())
+            .await
+            .map_err(PrepareError)?;
+        select.set_page_size(self.page_size);
+
+        let rows = stream::iter(pending_ranges.iter().copied())
+            .map(|range| self.backfill_range(session, &select, range, transform))
+            .buffer_unordered(self.parallelism)
+            .try_fold(0, |rows, range_rows| async move { Ok(rows + range_rows) })
+            .await?;
+
+        Ok(BackfillSummary {
+            ranges: ranges.len(),
+            resumed_ranges: ranges.len() - pending_ranges.len(),
+            rows,
+        })
+    }
+
+    async fn backfill_range(
+        &self,
+        session: &Session,
+        select: &PreparedStatement,
+        range: TokenRange,
+        transform: &dyn RowTransform,
+    ) -> Result<u64, BackfillError> {
+        let mut rows = session
+            .execute_iter(select.clone(), (range.start, range.end))
+            .await
+            .map_err(|err| ScanError(range, err))?;
+
+        let mut count = 0;
+        while let Some(row) = rows.next().await {
+            let row = row.map_err(|err| ScanError(range, err))?;
+            transform
+                .transform(session, row)
+                .await
+                .map_err(|err| TransformError(range, err))?;
+            count += 1;
+        }
+
+        self.complete_range(session, range, count).await?;
+        Ok(count)
+    }
+
+    async fn create_checkpoint_table(&self, session: &Session) -> Result<(), BackfillError> {
+        let query = format!(
+            "CREATE TABLE IF NOT EXISTS {keyspace}.{checkpoint_table} (
+                name         TEXT,
+                range_start  BIGINT,
+                range_end    BIGINT,
+                rows         BIGINT,
+                completed_at TIMESTAMP,
+                PRIMARY KEY (name, range_start, range_end)
+            );",
+            keyspace = self.keyspace,
+            checkpoint_table = *CHECKPOINT_TABLE_NAME
+        );
+
+        session.query(query, &[]).await.map_err(CheckpointError)?;
+        session
+            .await_schema_agreement()
+            .await
+            .map_err(CheckpointError)
+    }
+
+    async fn find_completed_ranges(
+        &self,
+        session: &Session,
+    ) -> Result<HashSet<TokenRange>, BackfillError> {
+        let query = format!(
+            "SELECT range_start, range_end FROM {}.{} WHERE name = ?;",
+            self.keyspace, *CHECKPOINT_TABLE_NAME
+        );
+
+        session
+            .query(query, (&self.name,))
+            .await
+            .map_err(CheckpointError)?
+            .rows_typed_or_empty::<(i64, i64)>()
+            .map(|row| {
+                row.map(|(start, end)| TokenRange { start, end })
+                    .map_err(|err| CheckpointError(QueryError::InvalidMessage(err.to_string())))
+            })
+            .collect()
+    }
+
+    async fn complete_range(
+        &self,
+        session: &Session,
+        range: TokenRange,
+        rows: u64,
+    ) -> Result<(), BackfillError> {
+        let query = format!(
+            "INSERT INTO {}.{} (name, range_start, range_end, rows, completed_at)
+                VALUES (?, ?, ?, ?, toTimestamp(now()));",
+            self.keyspace, *CHECKPOINT_TABLE_NAME
+        );
+
+        session
+            .query(query, (&self.name, range.start, range.end, rows as i64))
+            .await
+            .map(|_| ())
+            .map_err(CheckpointError)
+    }
+
+    fn select_statement(&self) -> String {
+        let columns = match self.columns.is_empty() {
+            true => "*".to_string(),
+            false => self.columns.join(", "),
+        };
+        let token = format!("token({})", self.partition_key.join(", "));
+
+        format!(
+            "SELECT {} FROM {}.{} WHERE {token} > ? AND {token} <= ?;",
+            columns, self.keyspace, self.table
+        )
+    }
+}
+
+/// Splits the Murmur3 ring into `count` contiguous ranges of nearly equal width; the minimum
+/// token is never assigned to a partition, so excluding it loses no row.
+pub fn split_token_ranges(count: usize) -> Vec<TokenRange> {
+    let count = count.max(1) as i128;
+    let min = i64::MIN as i128;
+    let width = i64::MAX as i128 - min;
+    let bound = |i: i128| (min + width * i / count) as i64;
+
+    (0..count)
+        .map(|i| TokenRange {
+            start: bound(i),
+            end: bound(i + 1),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(count, case(1), case(3), case(256))]
+    fn test_split_token_ranges(count: usize) {
+        // when
+        let ranges = split_token_ranges(count);
+
+        // then
+        assert_eq!(ranges.len(), count);
+        assert_eq!(ranges[0].start, i64::MIN);
+        assert_eq!(ranges[count - 1].end, i64::MAX);
+        assert!(ranges
+            .windows(2)
+            .all(|pair| pair[0].end == pair[1].start && pair[0].start < pair[0].end));
+    }
+
+    #[test]
+    fn test_select_statement() {
+        // given
+        let backfill = Backfill::new("split_names", "app", "users", &["tenant", "id"])
+            .with_columns(&["tenant", "id", "full_name"]);
+
+        // when
+        let statement = backfill.select_statement();
+
+        // then
+        assert_eq!(
+            statement,
+            "SELECT tenant, id, full_name FROM app.users \
+             WHERE token(tenant, id) > ? AND token(tenant, id) <= ?;"
+        );
+    }
+}
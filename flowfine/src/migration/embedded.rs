@@ -0,0 +1,144 @@
+use crate::config::{FilenameConvention, UnrecognizedFiles, VersionFormatting};
+use crate::migration::parser::parse_migrations;
+use crate::migration::source::{MigrationFile, MigrationSource};
+use crate::migration::{FileError, MigrationResult};
+
+/// Migration files compiled into the binary, usually by `flowfine_macros::embed_migrations!`,
+/// which validates them at build time so loading them needs no filesystem access.
+#[derive(Clone, Debug)]
+pub struct EmbeddedMigrations {
+    version_formatting: VersionFormatting,
+    filename_convention: EmbeddedFilenameConvention,
+    unrecognized_files: UnrecognizedFiles,
+    files: &'static [(&'static str, &'static str)],
+}
+
+/// `FilenameConvention` of embedded migrations, which can be built in a `static`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EmbeddedFilenameConvention {
+    pub versioned_prefix: &'static str,
+    pub undo_prefix: &'static str,
+    pub repeatable_prefix: &'static str,
+    pub separator: &'static str,
+    pub suffixes: &'static [&'static str],
+}
+
+impl EmbeddedFilenameConvention {
+    pub const DEFAULT: Self = Self {
+        versioned_prefix: "V",
+        undo_prefix: "U",
+        repeatable_prefix: "R",
+        separator: "__",
+        suffixes: &[".cql"],
+    };
+}
+
+impl From<EmbeddedFilenameConvention> for FilenameConvention {
+    fn from(convention: EmbeddedFilenameConvention) -> Self {
+        Self {
+            versioned_prefix: convention.versioned_prefix.to_string(),
+            undo_prefix: convention.undo_prefix.to_string(),
+            repeatable_prefix: convention.repeatable_prefix.to_string(),
+            separator: convention.separator.to_string(),
+            suffixes: convention.suffixes.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl EmbeddedMigrations {
+    /// Takes `(path, content)` pairs, paths being relative to the embedded directory.
+    pub const fn new(
+        version_formatting: VersionFormatting,
+        files: &'static [(&'static str, &'static str)],
+    ) -> Self {
+        Self::with_convention(
+            version_formatting,
+            EmbeddedFilenameConvention::DEFAULT,
+            UnrecognizedFiles::Error,
+            files,
+        )
+    }
+
+    /// Like `new`, for files named after another convention or containing unrecognized files.
+    pub const fn with_convention(
+        version_formatting: VersionFormatting,
+        filename_convention: EmbeddedFilenameConvention,
+        unrecognized_files: UnrecognizedFiles,
+        files: &'static [(&'static str, &'static str)],
+    ) -> Self {
+        Self {
+            version_formatting,
+            filename_convention,
+            unrecognized_files,
+            files,
+        }
+    }
+
+    /// Parses the files with the convention and strictness `embed_migrations!` validated them with.
+    pub fn load(&self) -> MigrationResult {
+        parse_migrations(
+            &self.migration_files(),
+            &self.version_formatting,
+            &self.filename_convention.into(),
+            self.unrecognized_files,
+        )
+    }
+
+    fn migration_files(&self) -> Vec<MigrationFile> {
+        self.files
+            .iter()
+            .map(|(path, content)| MigrationFile::new(path, content))
+            .collect()
+    }
+}
+
+impl MigrationSource for EmbeddedMigrations {
+    fn files(&self) -> Result<Vec<MigrationFile>, FileError> {
+        Ok(self.migration_files())
+    }
+
+    fn version_formatting(&self) -> Option<VersionFormatting> {
+        Some(self.version_formatting.clone())
+    }
+
+    fn filename_convention(&self) -> Option<FilenameConvention> {
+        Some(self.filename_convention.into())
+    }
+
+    fn unrecognized_files(&self) -> Option<UnrecognizedFiles> {
+        Some(self.unrecognized_files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static MIGRATIONS: EmbeddedMigrations = EmbeddedMigrations::new(
+        VersionFormatting::Numeric,
+        &[
+            ("V2__insert_foo.cql", "INSERT INTO foo (id) VALUES (1);"),
+            (
+                "V1__create_foo.cql",
+                "CREATE TABLE foo (id int PRIMARY KEY);",
+            ),
+            ("afterMigrate.cql", "SELECT * FROM foo;"),
+        ],
+    );
+
+    #[test]
+    fn test_load_embedded_migrations() {
+        // when
+        let result = MIGRATIONS.load();
+
+        // then
+        assert_eq!(result.callbacks().len(), 1);
+        let versions = result
+            .into_result()
+            .unwrap()
+            .into_iter()
+            .map(|migration| migration.version)
+            .collect::<Vec<_>>();
+        assert_eq!(versions, vec!["1", "2"]);
+    }
+}
@@ -30,6 +30,44 @@ pub fn delimit_queries(
     }
 }
 
+/// Finds the line, starting at 1, of the first token after any leading comments of each of the
+/// `queries` delimited from `content`.
+pub fn query_lines(content: &str, queries: &[String]) -> Vec<usize> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+
+    for query in queries {
+        match content[offset..].find(query.as_str()) {
+            Some(start) => {
+                let start = offset + start;
+                let first_token = start + leading_comments_len(query);
+                lines.push(content[..first_token].matches('\n').count() + 1);
+                offset = start + query.len();
+            }
+            None => break,
+        }
+    }
+
+    lines
+}
+
+/// Length of the comments and whitespace preceding the first token of `query`.
+fn leading_comments_len(query: &str) -> usize {
+    let mut rest = query;
+
+    loop {
+        let trimmed = rest.trim_start();
+        let skipped = if trimmed.starts_with("--") || trimmed.starts_with("//") {
+            trimmed.find('\n').map_or(trimmed.len(), |end| end + 1)
+        } else if trimmed.starts_with("/*") {
+            trimmed.find("*/").map_or(trimmed.len(), |end| end + 2)
+        } else {
+            return query.len() - trimmed.len();
+        };
+        rest = &trimmed[skipped..];
+    }
+}
+
 // todo: add support for comments
 fn find_valid_semicolons(content: &str) -> Vec<usize> {
     let mut quotes_captures = QUOTE_REGEX.find_iter(content).peekable();
@@ -90,6 +128,19 @@ mod tests {
         assert_eq!(delimit_queries("", query), Ok(expected_result));
     }
 
+    #[test]
+    fn test_query_lines() {
+        // given
+        let content = "-- users\nCREATE TABLE users (id int PRIMARY KEY);\n\n/* seed */\nINSERT INTO users (id)\n  VALUES (1);";
+        let queries = delimit_queries("", content).unwrap();
+
+        // when
+        let lines = query_lines(content, &queries);
+
+        // then
+        assert_eq!(lines, vec![2, 5]);
+    }
+
     #[rstest(query, expected_error,
         case("", NoSemicolonsFoundError("".to_string())),
         case("SELECT * FROM FOO", NoSemicolonsFoundError("".to_string())),
@@ -1,55 +1,157 @@
+use crate::config::FilenameConvention;
+use crate::migration::lexer::query_lines;
 use crate::migration::version::MigrationVersionKey;
 use crate::migration::MigrationParsingError::*;
+use serde::{Deserialize, Serialize};
+use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
+use std::io;
 use thiserror::Error;
 
+pub mod backfill;
+pub mod embedded;
 pub mod lexer;
 pub mod parser;
+pub mod rebase;
+pub mod rust;
+pub mod source;
 pub mod version;
 
 #[derive(Debug, Error)]
 pub enum FileError {
-    #[error("Directory not loaded")]
-    DirectoryNotLoadedError,
+    #[error("Directory {0} could not be read: {1}")]
+    DirectoryNotLoadedError(String, #[source] io::Error),
 
-    #[error("File not loaded")]
-    FileNotLoadedError,
+    #[error("Entry of directory {0} could not be read: {1}")]
+    FileNotLoadedError(String, #[source] io::Error),
+
+    #[error("File {0} could not be read: {1}")]
+    FileNotReadError(String, #[source] io::Error),
+
+    #[error("Archive {0} could not be read: {1}")]
+    ArchiveNotLoadedError(String, #[source] io::Error),
+
+    #[error("Ignore pattern {0} is invalid: {1}")]
+    InvalidIgnorePatternError(String, #[source] glob::PatternError),
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Error)]
 pub enum MigrationParsingError {
-    #[error("Duplicated migration.rs version for file: {0}")]
-    DuplicatedMigrationError(String),
+    #[error("Duplicated migration version in {0} and {1}")]
+    DuplicatedMigrationError(String, String),
 
-    #[error("Invalid migration.rs format for file: {0}")]
-    InvalidMigrationFormatError(String),
+    #[error("Invalid migration format for file {0}, expected {1}")]
+    InvalidMigrationFormatError(String, String),
 
     #[error("Invalid version format for file {0}")]
     InvalidVersionFormatError(String),
 
-    #[error("Missing migration.rs content for file {0}")]
+    #[error("Missing migration content for file {0}")]
     MissingMigrationContentError(String),
 
-    #[error("Missing semicolons in migration.rs content for file {0}")]
+    #[error("Missing semicolons in migration content for file {0}")]
     NoSemicolonsFoundError(String),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MigrationKind {
+    /// `V<version>__<name>.cql`, applied once in version order.
+    Versioned,
+    /// `R__<name>.cql`, re-applied whenever its checksum changes.
+    Repeatable,
+    /// `U<version>__<name>.cql`, reverts the versioned migration of the same version.
+    Undo,
+}
+
 #[derive(Clone, Debug)]
 pub struct Migration {
     pub filename: String,
+    pub kind: MigrationKind,
+    /// Empty for repeatable migrations.
     pub version: String,
-    pub version_key: MigrationVersionKey,
+    pub version_key: Option<MigrationVersionKey>,
     pub name: String,
     pub content: String,
     pub queries: Vec<String>,
 }
 
+impl Migration {
+    /// Line of the file, starting at 1, on which the query at `index` starts.
+    pub fn query_line(&self, index: usize) -> Option<usize> {
+        query_lines(&self.content, &self.queries)
+            .get(index)
+            .copied()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CallbackEvent {
+    BeforeMigrate,
+    BeforeEach,
+    AfterEach,
+    AfterEachError,
+    AfterMigrate,
+    AfterValidateError,
+}
+
+impl CallbackEvent {
+    const ALL: [CallbackEvent; 6] = [
+        CallbackEvent::BeforeMigrate,
+        CallbackEvent::BeforeEach,
+        CallbackEvent::AfterEach,
+        CallbackEvent::AfterEachError,
+        CallbackEvent::AfterMigrate,
+        CallbackEvent::AfterValidateError,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CallbackEvent::BeforeMigrate => "beforeMigrate",
+            CallbackEvent::BeforeEach => "beforeEach",
+            CallbackEvent::AfterEach => "afterEach",
+            CallbackEvent::AfterEachError => "afterEachError",
+            CallbackEvent::AfterMigrate => "afterMigrate",
+            CallbackEvent::AfterValidateError => "afterValidateError",
+        }
+    }
+
+    /// Matches filenames such as `beforeMigrate.cql` or `afterMigrate__refresh_grants.cql`.
+    pub fn from_filename(filename: &str, convention: &FilenameConvention) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| {
+            filename.strip_prefix(event.name()).is_some_and(|rest| {
+                rest.starts_with(convention.separator.as_str())
+                    || convention.suffix(rest) == Some(rest)
+            })
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CallbackScript {
+    pub filename: String,
+    pub event: CallbackEvent,
+    pub description: String,
+    pub content: String,
+    pub queries: Vec<String>,
+}
+
 pub struct MigrationResult {
     errors: Vec<MigrationParsingError>,
     migrations: Vec<Migration>,
+    callbacks: Vec<CallbackScript>,
+    unrecognized_files: Vec<String>,
 }
 
 impl MigrationResult {
+    pub fn callbacks(&self) -> &[CallbackScript] {
+        &self.callbacks
+    }
+
+    /// Paths of the files skipped with `UnrecognizedFiles::Warn`.
+    pub fn unrecognized_files(&self) -> &[String] {
+        &self.unrecognized_files
+    }
+
     pub fn into_result(self) -> Result<Vec<Migration>, Vec<MigrationParsingError>> {
         if self.errors.is_empty() {
             Ok(self.migrations)
@@ -60,7 +162,11 @@ impl MigrationResult {
 }
 
 pub struct MigrationStack {
-    migrations: BTreeMap<MigrationVersionKey, Migration>,
+    migrations: BTreeMap<MigrationVersionKey, (String, Migration)>,
+    repeatable_migrations: BTreeMap<String, (String, Migration)>,
+    undo_migrations: BTreeMap<MigrationVersionKey, (String, Migration)>,
+    callbacks: BTreeMap<String, CallbackScript>,
+    unrecognized_files: Vec<String>,
     errors: Vec<MigrationParsingError>,
 }
 
@@ -68,17 +174,46 @@ impl MigrationStack {
     fn new() -> Self {
         MigrationStack {
             migrations: BTreeMap::new(),
+            repeatable_migrations: BTreeMap::new(),
+            undo_migrations: BTreeMap::new(),
+            callbacks: BTreeMap::new(),
+            unrecognized_files: Vec::new(),
             errors: Vec::new(),
         }
     }
 
-    fn push_migration(&mut self, migration: Migration) {
-        let filename = migration.filename.clone();
-        let version_key = migration.version_key.clone();
+    fn push_callback(&mut self, path: &str, callback: CallbackScript) {
+        self.callbacks.insert(path.to_string(), callback);
+    }
+
+    /// Keeps the migration found first when two share a version, reporting both paths.
+    fn push_migration(&mut self, path: &str, migration: Migration) {
+        let migrations = match (migration.kind, &migration.version_key) {
+            (MigrationKind::Repeatable, _) => insert_unique(
+                &mut self.repeatable_migrations,
+                migration.name.clone(),
+                path,
+                migration,
+            ),
+            (MigrationKind::Undo, Some(version_key)) => insert_unique(
+                &mut self.undo_migrations,
+                version_key.clone(),
+                path,
+                migration,
+            ),
+            (_, Some(version_key)) => {
+                insert_unique(&mut self.migrations, version_key.clone(), path, migration)
+            }
+            (_, None) => Ok(()),
+        };
+
+        if let Err(existing_path) = migrations {
+            self.push_error(DuplicatedMigrationError(path.to_string(), existing_path));
+        }
+    }
 
-        self.migrations
-            .insert(version_key, migration)
-            .map(|_| self.push_error(DuplicatedMigrationError(filename)));
+    fn push_unrecognized_file(&mut self, path: &str) {
+        self.unrecognized_files.push(path.to_string());
     }
 
     fn push_error(&mut self, error: MigrationParsingError) {
@@ -88,7 +223,31 @@ impl MigrationStack {
     fn into_result(self) -> MigrationResult {
         MigrationResult {
             errors: self.errors,
-            migrations: self.migrations.into_values().collect(),
+            migrations: self
+                .migrations
+                .into_values()
+                .chain(self.repeatable_migrations.into_values())
+                .chain(self.undo_migrations.into_values())
+                .map(|(_, migration)| migration)
+                .collect(),
+            callbacks: self.callbacks.into_values().collect(),
+            unrecognized_files: self.unrecognized_files,
+        }
+    }
+}
+
+/// Returns the path of the migration already stored under `key`, if any.
+fn insert_unique<K: Ord>(
+    migrations: &mut BTreeMap<K, (String, Migration)>,
+    key: K,
+    path: &str,
+    migration: Migration,
+) -> Result<(), String> {
+    match migrations.entry(key) {
+        Entry::Occupied(entry) => Err(entry.get().0.clone()),
+        Entry::Vacant(entry) => {
+            entry.insert((path.to_string(), migration));
+            Ok(())
         }
     }
 }
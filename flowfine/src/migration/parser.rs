@@ -1,41 +1,147 @@
-use crate::config::VersionFormatting;
+use crate::config::{FilenameConvention, UnrecognizedFiles, VersionFormatting};
 use crate::migration::lexer::delimit_queries;
+use crate::migration::rust::{self, RustMigration};
+use crate::migration::source::{DirectorySource, MigrationFile, MigrationSource};
 use crate::migration::version::MigrationVersionKey;
-use crate::migration::FileError::*;
 use crate::migration::*;
-use std::fs::{read_dir, read_to_string, DirEntry};
+use std::sync::Arc;
 
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(version_formatting)))]
 pub fn get_migrations(
     directory_path: &str,
     version_formatting: &VersionFormatting,
 ) -> Result<MigrationResult, FileError> {
-    let entries = read_dir(directory_path).map_err(|_err| DirectoryNotLoadedError)?;
-    let mut migration_stack = MigrationStack::new();
+    load_migrations(
+        &DirectorySource::new(directory_path),
+        version_formatting,
+        &FilenameConvention::default(),
+        UnrecognizedFiles::Error,
+    )
+}
+
+pub fn load_migrations(
+    source: &dyn MigrationSource,
+    version_formatting: &VersionFormatting,
+    filename_convention: &FilenameConvention,
+    unrecognized_files: UnrecognizedFiles,
+) -> Result<MigrationResult, FileError> {
+    Ok(parse_migrations(
+        &source.files()?,
+        version_formatting,
+        filename_convention,
+        unrecognized_files,
+    ))
+}
+
+/// Parses files held in memory; versions must be unique across all of them.
+/// Files without one of the convention's suffixes are handled according to `unrecognized_files`.
+pub fn parse_migrations(
+    files: &[MigrationFile],
+    version_formatting: &VersionFormatting,
+    filename_convention: &FilenameConvention,
+    unrecognized_files: UnrecognizedFiles,
+) -> MigrationResult {
+    parse_with_rust_migrations(
+        files,
+        &[],
+        version_formatting,
+        filename_convention,
+        unrecognized_files,
+    )
+}
 
-    for entry in entries {
-        let entry = entry.map_err(|_err| FileNotLoadedError)?;
+/// Parses files like `parse_migrations`, stacking `rust_migrations` in the same version order.
+pub fn parse_with_rust_migrations(
+    files: &[MigrationFile],
+    rust_migrations: &[Arc<dyn RustMigration>],
+    version_formatting: &VersionFormatting,
+    filename_convention: &FilenameConvention,
+    unrecognized_files: UnrecognizedFiles,
+) -> MigrationResult {
+    let mut migration_stack = MigrationStack::new();
 
-        match parse_migration(&entry, version_formatting) {
-            Ok(migration) => migration_stack.push_migration(migration),
+    for rust_migration in rust_migrations {
+        match rust::to_migration(
+            rust_migration.as_ref(),
+            version_formatting,
+            filename_convention,
+        ) {
+            Ok(migration) => migration_stack.push_migration(&migration.filename.clone(), migration),
             Err(err) => migration_stack.push_error(err),
         }
     }
 
-    Ok(migration_stack.into_result())
+    for file in files {
+        let filename = file.filename.as_str();
+        let content = file.content.as_str();
+
+        if filename_convention.suffix(filename).is_none() {
+            match unrecognized_files {
+                UnrecognizedFiles::Ignore => continue,
+                UnrecognizedFiles::Warn => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(path = %file.path, "skipped unrecognized file");
+
+                    migration_stack.push_unrecognized_file(&file.path);
+                    continue;
+                }
+                UnrecognizedFiles::Error => {}
+            }
+        }
+
+        if let Some(event) = CallbackEvent::from_filename(filename, filename_convention) {
+            match parse_callback(filename, content, event, filename_convention) {
+                Ok(callback) => migration_stack.push_callback(&file.path, callback),
+                Err(err) => migration_stack.push_error(err),
+            }
+            continue;
+        }
+
+        match parse_migration(filename, content, version_formatting, filename_convention) {
+            Ok(migration) => migration_stack.push_migration(&file.path, migration),
+            Err(err) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %err, filename = %filename, "invalid migration file");
+
+                migration_stack.push_error(err)
+            }
+        }
+    }
+
+    migration_stack.into_result()
 }
 
 fn parse_migration(
-    path: &DirEntry,
+    filename: &str,
+    content: &str,
     version_formatting: &VersionFormatting,
+    convention: &FilenameConvention,
 ) -> Result<Migration, MigrationParsingError> {
-    let filename = parse_migration_filename(path);
-    let (version, version_key) = parse_migration_version(&filename, version_formatting)?;
-    let name = parse_migration_name(&filename)?;
-    let content = parse_migration_content(path)?;
+    let filename = filename.to_string();
+    let (kind, stem) = parse_migration_stem(&filename, convention)?;
+    let (version, version_key, name) = match kind {
+        MigrationKind::Repeatable => {
+            let name = stem
+                .strip_prefix(convention.separator.as_str())
+                .ok_or_else(|| invalid_format(&filename, convention.pattern(kind)))?;
+            (String::new(), None, name)
+        }
+        _ => {
+            let (version, name) = stem
+                .split_once(convention.separator.as_str())
+                .ok_or_else(|| invalid_format(&filename, convention.pattern(kind)))?;
+            let version_key = MigrationVersionKey::new(version_formatting, version)
+                .ok_or(InvalidVersionFormatError(filename.clone()))?;
+            (version.to_string(), Some(version_key), name)
+        }
+    };
+    let name = name.replace('_', " ");
+    let content = parse_migration_content(&filename, content)?;
     let queries = delimit_queries(&filename, &content)?;
 
     let migration = Migration {
         filename,
+        kind,
         version,
         version_key,
         name,
@@ -46,49 +152,83 @@ fn parse_migration(
     Ok(migration)
 }
 
-fn parse_migration_filename(path: &DirEntry) -> String {
-    match path.file_name().into_string() {
-        Ok(filename) => filename,
-        Err(filename) => filename.to_string_lossy().to_string(),
+/// Kind, version and description of a versioned or undo migration, read from its filename only.
+pub(crate) fn split_versioned_filename<'a>(
+    filename: &'a str,
+    convention: &FilenameConvention,
+) -> Option<(MigrationKind, &'a str, &'a str)> {
+    match parse_migration_stem(filename, convention).ok()? {
+        (MigrationKind::Repeatable, _) => None,
+        (kind, stem) => {
+            let (version, description) = stem.split_once(convention.separator.as_str())?;
+            Some((kind, version, description))
+        }
     }
 }
 
-fn parse_migration_version(
-    filename: &str,
-    version_formatting: &VersionFormatting,
-) -> Result<(String, MigrationVersionKey), MigrationParsingError> {
-    let start = 1;
-    let end = filename
-        .find("__")
-        .ok_or(InvalidMigrationFormatError(filename.to_string()))?;
-    let version = &filename[start..end];
-
-    MigrationVersionKey::new(version_formatting, version)
-        .map(|version_key| (version.to_string(), version_key))
-        .ok_or(InvalidVersionFormatError(filename.to_string()))
+/// Splits off the prefix and the suffix, leaving e.g. `1__create_users` of `V1__create_users.cql`.
+fn parse_migration_stem<'a>(
+    filename: &'a str,
+    convention: &FilenameConvention,
+) -> Result<(MigrationKind, &'a str), MigrationParsingError> {
+    let (kind, suffix) = convention
+        .kind(filename)
+        .zip(convention.suffix(filename))
+        .ok_or_else(|| invalid_format(filename, convention.patterns()))?;
+
+    filename
+        .strip_suffix(suffix)
+        .and_then(|rest| rest.strip_prefix(convention.prefix(kind)))
+        .map(|stem| (kind, stem))
+        .ok_or_else(|| invalid_format(filename, convention.pattern(kind)))
 }
 
-fn parse_migration_name(filename: &str) -> Result<String, MigrationParsingError> {
-    let extension = ".cql";
+fn parse_callback(
+    filename: &str,
+    content: &str,
+    event: CallbackEvent,
+    convention: &FilenameConvention,
+) -> Result<CallbackScript, MigrationParsingError> {
+    let filename = filename.to_string();
+    let description = parse_callback_description(&filename, event, convention)?;
+    let content = parse_migration_content(&filename, content)?;
+    let queries = delimit_queries(&filename, &content)?;
 
-    if !filename.ends_with(extension) {
-        return Err(InvalidMigrationFormatError(filename.to_string()));
-    }
+    let callback = CallbackScript {
+        filename,
+        event,
+        description,
+        content,
+        queries,
+    };
+
+    Ok(callback)
+}
 
-    let migration_name = filename
-        .find("__")
-        .and_then(|start| filename.rfind(extension).map(|end| (start, end)))
-        .map(|(start, end)| &filename[start + 2..end])
-        .ok_or(InvalidMigrationFormatError(filename.to_string()))?;
+fn parse_callback_description(
+    filename: &str,
+    event: CallbackEvent,
+    convention: &FilenameConvention,
+) -> Result<String, MigrationParsingError> {
+    convention
+        .suffix(filename)
+        .and_then(|suffix| filename.strip_suffix(suffix))
+        .and_then(|rest| rest.strip_prefix(event.name()))
+        .map(|rest| {
+            rest.trim_start_matches(convention.separator.as_str())
+                .replace('_', " ")
+        })
+        .ok_or_else(|| invalid_format(filename, convention.callback_pattern(event.name())))
+}
 
-    Ok(migration_name.replace("_", " "))
+fn invalid_format(filename: &str, expected: String) -> MigrationParsingError {
+    InvalidMigrationFormatError(filename.to_string(), expected)
 }
 
-fn parse_migration_content(path: &DirEntry) -> Result<String, MigrationParsingError> {
-    match read_to_string(path.path()) {
-        Ok(content) if !content.trim().is_empty() => Ok(content),
-        Ok(_) => Err(MissingMigrationContentError(parse_migration_filename(path))),
-        Err(_) => Err(MissingMigrationContentError(parse_migration_filename(path))),
+fn parse_migration_content(filename: &str, content: &str) -> Result<String, MigrationParsingError> {
+    match content.trim().is_empty() {
+        false => Ok(content.to_string()),
+        true => Err(MissingMigrationContentError(filename.to_string())),
     }
 }
 
@@ -96,6 +236,7 @@ fn parse_migration_content(path: &DirEntry) -> Result<String, MigrationParsingEr
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::migration::source::InMemorySource;
     use itertools::Itertools;
     use rstest::rstest;
     use std::collections::HashSet;
@@ -138,18 +279,302 @@ mod tests {
         assert!(result.is_ok());
         let expected = vec![
             InvalidVersionFormatError("V__invalid_migration_version.cql".to_string()),
-            InvalidMigrationFormatError("V1_invalid_migration_underscore.cql".to_string()),
+            InvalidMigrationFormatError(
+                "V1_invalid_migration_underscore.cql".to_string(),
+                "V<version>__<description>.cql".to_string(),
+            ),
             MissingMigrationContentError("V1__invalid_migration_missing_content.cql".to_string()),
-            InvalidMigrationFormatError("V1__invalid_migration_missing_extension.".to_string()),
+            InvalidMigrationFormatError(
+                "V1__invalid_migration_missing_extension.".to_string(),
+                FilenameConvention::default().patterns(),
+            ),
             NoSemicolonsFoundError("V1__invalid_migration_missing_semicolon.cql".to_string()),
         ];
 
         assert_errors_any_order(expected, result.unwrap().errors);
     }
 
+    #[test]
+    fn test_callback_migrations() {
+        // given
+        let version_formatting = VersionFormatting::Numeric;
+        let path = "./tests/data/unit/callback_migrations";
+
+        // when
+        let result = get_migrations(path, &version_formatting);
+
+        // then
+        assert!(result.is_ok());
+        let result = result.unwrap();
+        let callbacks = result
+            .callbacks()
+            .iter()
+            .map(|callback| (callback.event, callback.description.as_str()))
+            .collect_vec();
+
+        assert_eq!(
+            callbacks,
+            vec![
+                (CallbackEvent::AfterMigrate, "refresh grants"),
+                (CallbackEvent::BeforeMigrate, ""),
+            ]
+        );
+        assert_migrations(vec!["V1__migration.cql"], result.into_result().unwrap());
+    }
+
+    #[test]
+    fn test_repeatable_and_undo_migrations() {
+        // given
+        let version_formatting = VersionFormatting::Numeric;
+        let path = "./tests/data/unit/repeatable_migrations";
+
+        // when
+        let result = get_migrations(path, &version_formatting);
+
+        // then
+        assert!(result.is_ok());
+        let migrations = result.unwrap().into_result().unwrap();
+        let kinds = migrations
+            .iter()
+            .map(|migration| (migration.kind, migration.version.as_str()))
+            .collect_vec();
+
+        assert_eq!(
+            kinds,
+            vec![
+                (MigrationKind::Versioned, "1"),
+                (MigrationKind::Repeatable, ""),
+                (MigrationKind::Undo, "1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_in_memory_migrations() {
+        // given
+        let source = InMemorySource::new()
+            .with_file(
+                "V1__create_foo.cql",
+                "CREATE TABLE foo (id int PRIMARY KEY);",
+            )
+            .with_file(
+                "V1__create_bar.cql",
+                "CREATE TABLE bar (id int PRIMARY KEY);",
+            )
+            .with_file("V2__insert_foo.cql", "INSERT INTO foo (id) VALUES (1);");
+
+        // when
+        let result = load_migrations(
+            &source,
+            &VersionFormatting::Numeric,
+            &FilenameConvention::default(),
+            UnrecognizedFiles::Error,
+        );
+
+        // then
+        assert_errors_any_order(
+            vec![DuplicatedMigrationError(
+                "V1__create_bar.cql".to_string(),
+                "V1__create_foo.cql".to_string(),
+            )],
+            result.unwrap().errors,
+        );
+    }
+
+    #[test]
+    fn test_duplicated_version_across_locations() {
+        // given
+        let source = vec![
+            DirectorySource::new("./tests/data/unit/nested_migrations"),
+            DirectorySource::new("./tests/data/unit/shared_migrations"),
+        ];
+
+        // when
+        let result = load_migrations(
+            &source,
+            &VersionFormatting::Numeric,
+            &FilenameConvention::default(),
+            UnrecognizedFiles::Error,
+        );
+
+        // then
+        let errors = result.unwrap().into_result().unwrap_err();
+        let paths = match errors.as_slice() {
+            [DuplicatedMigrationError(path, other_path)] => {
+                let mut paths = vec![path.as_str(), other_path.as_str()];
+                paths.sort();
+                paths
+            }
+            _ => panic!("expected one duplicated version, got {:?}", errors),
+        };
+        assert_eq!(
+            paths,
+            vec![
+                "./tests/data/unit/nested_migrations/billing/V2__create_invoices.cql",
+                "./tests/data/unit/shared_migrations/V2__create_users.cql",
+            ]
+        );
+    }
+
+    #[rstest(unrecognized_files, expected_unrecognized, expected_errors,
+        case(UnrecognizedFiles::Ignore, vec![], vec![]),
+        case(UnrecognizedFiles::Warn, vec!["README.md"], vec![]),
+        case(UnrecognizedFiles::Error, vec![], vec![InvalidMigrationFormatError("README.md".to_string(), FilenameConvention::default().patterns())]),
+    )]
+    fn test_unrecognized_files(
+        unrecognized_files: UnrecognizedFiles,
+        expected_unrecognized: Vec<&str>,
+        expected_errors: Vec<MigrationParsingError>,
+    ) {
+        // given
+        let source = InMemorySource::new()
+            .with_file(
+                "V1__create_foo.cql",
+                "CREATE TABLE foo (id int PRIMARY KEY);",
+            )
+            .with_file("README.md", "# Migrations");
+
+        // when
+        let result = load_migrations(
+            &source,
+            &VersionFormatting::Numeric,
+            &FilenameConvention::default(),
+            unrecognized_files,
+        )
+        .unwrap();
+
+        // then
+        assert_eq!(
+            result.unrecognized_files(),
+            expected_unrecognized.as_slice()
+        );
+        assert_eq!(result.errors, expected_errors);
+        assert_eq!(result.migrations.len(), 1);
+    }
+
+    #[test]
+    fn test_custom_filename_convention() {
+        // given
+        let convention = FilenameConvention {
+            versioned_prefix: "M".to_string(),
+            undo_prefix: "UNDO".to_string(),
+            repeatable_prefix: "EACH".to_string(),
+            separator: "-".to_string(),
+            suffixes: vec![".cql".to_string(), ".cql.tmpl".to_string()],
+        };
+        let source = InMemorySource::new()
+            .with_file(
+                "M1-create_foo.cql",
+                "CREATE TABLE foo (id int PRIMARY KEY);",
+            )
+            .with_file("UNDO1-create_foo.cql.tmpl", "DROP TABLE foo;")
+            .with_file("EACH-refresh_views.cql", "SELECT * FROM foo;")
+            .with_file("afterMigrate-grants.cql", "SELECT * FROM foo;")
+            .with_file("M2__insert_foo.cql", "INSERT INTO foo (id) VALUES (1);");
+
+        // when
+        let result = load_migrations(
+            &source,
+            &VersionFormatting::Numeric,
+            &convention,
+            UnrecognizedFiles::Error,
+        )
+        .unwrap();
+
+        // then
+        assert_eq!(result.callbacks()[0].description, "grants");
+        let migrations = result
+            .migrations
+            .iter()
+            .map(|migration| {
+                (
+                    migration.kind,
+                    migration.version.as_str(),
+                    migration.name.as_str(),
+                )
+            })
+            .collect_vec();
+        assert_eq!(
+            migrations,
+            vec![
+                (MigrationKind::Versioned, "1", "create foo"),
+                (MigrationKind::Repeatable, "", "refresh views"),
+                (MigrationKind::Undo, "1", "create foo"),
+            ]
+        );
+        assert_eq!(
+            result.errors,
+            vec![InvalidMigrationFormatError(
+                "M2__insert_foo.cql".to_string(),
+                "M<version>-<description>{.cql,.cql.tmpl}".to_string(),
+            )]
+        );
+    }
+
+    #[test]
+    fn test_stack_rust_migrations() {
+        // given
+        let files = vec![
+            MigrationFile::new(
+                "V1__create_foo.cql",
+                "CREATE TABLE foo (id int PRIMARY KEY);",
+            ),
+            MigrationFile::new("V3__drop_bar.cql", "DROP TABLE bar;"),
+        ];
+        let rust_migrations: Vec<Arc<dyn RustMigration>> = vec![
+            Arc::new(NoopRustMigration("2", "backfill foo")),
+            Arc::new(NoopRustMigration("3", "split bar")),
+        ];
+
+        // when
+        let result = parse_with_rust_migrations(
+            &files,
+            &rust_migrations,
+            &VersionFormatting::Numeric,
+            &FilenameConvention::default(),
+            UnrecognizedFiles::Error,
+        );
+
+        // then
+        assert_migrations(
+            vec![
+                "V1__create_foo.cql",
+                "V2__backfill_foo.rs",
+                "V3__split_bar.rs",
+            ],
+            result.migrations,
+        );
+        assert_eq!(
+            result.errors,
+            vec![DuplicatedMigrationError(
+                "V3__drop_bar.cql".to_string(),
+                "V3__split_bar.rs".to_string(),
+            )]
+        );
+    }
+
+    struct NoopRustMigration(&'static str, &'static str);
+
+    #[async_trait::async_trait]
+    impl RustMigration for NoopRustMigration {
+        fn version(&self) -> &str {
+            self.0
+        }
+
+        fn description(&self) -> &str {
+            self.1
+        }
+
+        async fn migrate(
+            &self,
+            _session: &scylla::Session,
+        ) -> Result<(), rust::RustMigrationError> {
+            Ok(())
+        }
+    }
+
     fn assert_migrations(expected: Vec<&str>, actual: Vec<Migration>) {
         let actual_filenames = actual
-            .into_iter()
             .into_iter()
             .map(|migration| migration.filename)
             .collect_vec();
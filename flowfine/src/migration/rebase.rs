@@ -0,0 +1,389 @@
+use crate::config::{FilenameConvention, VersionFormatting};
+use crate::migration::parser::split_versioned_filename;
+use crate::migration::rebase::RebaseError::*;
+use crate::migration::rust::RustMigration;
+use crate::migration::source::MigrationFile;
+use crate::migration::version::{compare_versions, next_version, version_after};
+use crate::migration::{CallbackEvent, MigrationKind};
+use crate::runner::plan::AppliedState;
+use crate::runner::AppliedMigration;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::{fs, io};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RebaseError {
+    #[error("No version follows {0}, {1} has to be renamed by hand")]
+    UnderivableVersionError(String, String),
+
+    #[error("File {0} could not be renamed to {1}: {2}")]
+    RenameError(String, String, #[source] io::Error),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileRename {
+    pub from: String,
+    pub to: String,
+}
+
+/// New version of an unapplied migration whose version is applied, used by another local
+/// migration or older than the latest applied one, e.g. after merging two branches.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionRename {
+    pub version: String,
+    pub new_version: String,
+    /// The migration file followed by its undo migrations, those with the same description.
+    pub files: Vec<FileRename>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RebasePlan {
+    pub renames: Vec<VersionRename>,
+}
+
+impl RebasePlan {
+    pub fn is_empty(&self) -> bool {
+        self.renames.is_empty()
+    }
+
+    /// Renames the files, refusing to overwrite existing ones.
+    pub fn apply(&self) -> Result<(), RebaseError> {
+        for file in self.renames.iter().flat_map(|rename| &rename.files) {
+            if Path::new(&file.to).exists() {
+                let err = io::Error::new(io::ErrorKind::AlreadyExists, "file already exists");
+                return Err(RenameError(file.from.clone(), file.to.clone(), err));
+            }
+            fs::rename(&file.from, &file.to)
+                .map_err(|err| RenameError(file.from.clone(), file.to.clone(), err))?;
+        }
+
+        Ok(())
+    }
+}
+
+struct LocalMigration<'a> {
+    file: &'a MigrationFile,
+    kind: MigrationKind,
+    version: &'a str,
+    description: &'a str,
+}
+
+/// Proposes versions after the latest applied or local one for the unapplied migrations of
+/// `files` that would otherwise be reported as duplicated or skipped. Among local migrations
+/// sharing a version the applied one, or else the first by path, keeps it.
+pub fn plan_rebase(
+    files: &[MigrationFile],
+    applied_migrations: &[AppliedMigration],
+    rust_migrations: &[Arc<dyn RustMigration>],
+    version_formatting: &VersionFormatting,
+    filename_convention: &FilenameConvention,
+) -> Result<RebasePlan, RebaseError> {
+    let compare = |a: &str, b: &str| compare_versions(version_formatting, a, b);
+    let state = AppliedState::from_history(applied_migrations, filename_convention);
+    let applied_filenames = applied_migrations
+        .iter()
+        .filter(|applied_migration| state.versions.contains(&applied_migration.version))
+        .map(|applied_migration| applied_migration.filename.as_str())
+        .collect::<HashSet<_>>();
+    let latest_applied = state.versions.iter().max_by(|a, b| compare(a, b));
+
+    let mut local_migrations = files
+        .iter()
+        .filter(|file| CallbackEvent::from_filename(&file.filename, filename_convention).is_none())
+        .filter_map(|file| {
+            let (kind, version, description) =
+                split_versioned_filename(&file.filename, filename_convention)?;
+            Some(LocalMigration {
+                file,
+                kind,
+                version,
+                description,
+            })
+        })
+        .collect::<Vec<_>>();
+    local_migrations
+        .sort_by(|a, b| compare(a.version, b.version).then_with(|| a.file.path.cmp(&b.file.path)));
+
+    let mut taken_versions = state.versions.clone();
+    taken_versions.extend(
+        rust_migrations
+            .iter()
+            .map(|rust_migration| rust_migration.version().to_string()),
+    );
+    let mut conflicting_migrations = Vec::new();
+    for migration in &local_migrations {
+        if migration.kind != MigrationKind::Versioned
+            || applied_filenames.contains(migration.file.filename.as_str())
+        {
+            continue;
+        }
+
+        let is_outdated =
+            latest_applied.is_some_and(|latest| compare(migration.version, latest).is_le());
+        let is_taken = taken_versions
+            .iter()
+            .any(|version| compare(version, migration.version).is_eq());
+        match is_outdated || is_taken {
+            true => conflicting_migrations.push(migration),
+            false => taken_versions.push(migration.version.to_string()),
+        }
+    }
+
+    let mut renames = Vec::new();
+    for migration in conflicting_migrations {
+        let latest = taken_versions
+            .iter()
+            .max_by(|a, b| compare(a, b))
+            .cloned()
+            .unwrap_or_default();
+        let new_version = next_version(
+            version_formatting,
+            taken_versions.iter().map(String::as_str),
+        )
+        .filter(|version| compare(&latest, version).is_lt())
+        .or_else(|| version_after(version_formatting, &latest))
+        .ok_or_else(|| UnderivableVersionError(latest.clone(), migration.file.path.clone()))?;
+
+        let undo_migrations = local_migrations.iter().filter(|undo_migration| {
+            undo_migration.kind == MigrationKind::Undo
+                && undo_migration.description == migration.description
+                && compare(undo_migration.version, migration.version).is_eq()
+        });
+        let files = [migration]
+            .into_iter()
+            .chain(undo_migrations)
+            .map(|local_migration| FileRename {
+                from: local_migration.file.path.clone(),
+                to: renamed_path(local_migration, &new_version, filename_convention),
+            })
+            .collect();
+
+        taken_versions.push(new_version.clone());
+        renames.push(VersionRename {
+            version: migration.version.to_string(),
+            new_version,
+            files,
+        });
+    }
+
+    Ok(RebasePlan { renames })
+}
+
+fn renamed_path(
+    migration: &LocalMigration,
+    new_version: &str,
+    filename_convention: &FilenameConvention,
+) -> String {
+    let prefix = filename_convention.prefix(migration.kind);
+    let rest = &migration.file.filename[prefix.len() + migration.version.len()..];
+    let filename = format!("{}{}{}", prefix, new_version, rest);
+
+    Path::new(&migration.file.path)
+        .with_file_name(filename)
+        .to_string_lossy()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use rstest::rstest;
+
+    fn file(path: &str) -> MigrationFile {
+        MigrationFile::new(path, "SELECT now() FROM system.local;")
+    }
+
+    fn applied(version: &str, filename: &str) -> AppliedMigration {
+        AppliedMigration {
+            id: filename.to_string(),
+            version: version.to_string(),
+            name: "migration".to_string(),
+            filename: filename.to_string(),
+            checksum: String::new(),
+            applied_at: Duration::zero(),
+            success: true,
+            interrupted: Some(false),
+            execution_time: Some(0),
+            executed_by: Some("flowfine".to_string()),
+            statement_count: Some(1),
+            flowfine_version: None,
+        }
+    }
+
+    fn renamed_files(plan: &RebasePlan) -> Vec<(&str, &str)> {
+        plan.renames
+            .iter()
+            .flat_map(|rename| &rename.files)
+            .map(|file| (file.from.as_str(), file.to.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_rebase_versions_taken_after_merge() {
+        // given
+        let files = [
+            file("migrations/V1__create_users.cql"),
+            file("migrations/V2__add_email.cql"),
+            file("migrations/V2__create_orders.cql"),
+            file("migrations/U2__create_orders.cql"),
+            file("migrations/V3__add_index.cql"),
+            file("migrations/V3__add_phone.cql"),
+            file("migrations/R__views.cql"),
+        ];
+        let applied_migrations = [
+            applied("1", "V1__create_users.cql"),
+            applied("2", "V2__add_email.cql"),
+        ];
+
+        // when
+        let plan = plan_rebase(
+            &files,
+            &applied_migrations,
+            &[],
+            &VersionFormatting::Numeric,
+            &FilenameConvention::default(),
+        )
+        .unwrap();
+
+        // then
+        assert_eq!(
+            renamed_files(&plan),
+            vec![
+                (
+                    "migrations/V2__create_orders.cql",
+                    "migrations/V4__create_orders.cql"
+                ),
+                (
+                    "migrations/U2__create_orders.cql",
+                    "migrations/U4__create_orders.cql"
+                ),
+                (
+                    "migrations/V3__add_phone.cql",
+                    "migrations/V5__add_phone.cql"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rebase_versions_past_nine() {
+        // given
+        let files = [
+            file("migrations/V8__create_users.cql"),
+            file("migrations/V9__add_email.cql"),
+            file("migrations/V9__create_orders.cql"),
+            file("migrations/V9__add_phone.cql"),
+        ];
+        let applied_migrations = [
+            applied("8", "V8__create_users.cql"),
+            applied("9", "V9__add_email.cql"),
+        ];
+
+        // when
+        let plan = plan_rebase(
+            &files,
+            &applied_migrations,
+            &[],
+            &VersionFormatting::Numeric,
+            &FilenameConvention::default(),
+        )
+        .unwrap();
+
+        // then
+        assert_eq!(
+            renamed_files(&plan),
+            vec![
+                (
+                    "migrations/V9__add_phone.cql",
+                    "migrations/V10__add_phone.cql"
+                ),
+                (
+                    "migrations/V9__create_orders.cql",
+                    "migrations/V11__create_orders.cql"
+                ),
+            ]
+        );
+    }
+
+    #[rstest(version_formatting, files, expected_result,
+    case(VersionFormatting::Numeric, vec!["V1.4__a.cql", "V1.2__b.cql"], vec![("V1.2__b.cql", "V2__b.cql")]),
+    case(VersionFormatting::Numeric, vec!["V10__a.cql", "V9__b.cql"], vec![("V9__b.cql", "V11__b.cql")]),
+    case(VersionFormatting::Datetime,
+        vec!["V29991001120000__a.cql", "V29990901120000__b.cql"],
+        vec![("V29990901120000__b.cql", "V29991001120001__b.cql")]),
+    )]
+    fn test_rebase_versions_older_than_latest_applied(
+        version_formatting: VersionFormatting,
+        files: Vec<&str>,
+        expected_result: Vec<(&str, &str)>,
+    ) {
+        // given
+        let (version, _) = files[0][1..].split_once("__").unwrap();
+        let applied_migrations = [applied(version, files[0])];
+        let files = files.into_iter().map(file).collect::<Vec<_>>();
+
+        // when
+        let plan = plan_rebase(
+            &files,
+            &applied_migrations,
+            &[],
+            &version_formatting,
+            &FilenameConvention::default(),
+        )
+        .unwrap();
+
+        // then
+        assert_eq!(renamed_files(&plan), expected_result);
+    }
+
+    #[test]
+    fn test_leave_consistent_migrations() {
+        // given
+        let files = [file("V1__a.cql"), file("V2__b.cql"), file("V3__c.cql")];
+        let applied_migrations = [applied("1", "V1__a.cql")];
+
+        // when
+        let plan = plan_rebase(
+            &files,
+            &applied_migrations,
+            &[],
+            &VersionFormatting::Numeric,
+            &FilenameConvention::default(),
+        )
+        .unwrap();
+
+        // then
+        assert!(plan.is_empty());
+    }
+
+    #[test]
+    fn test_apply_rebase() {
+        // given
+        let directory =
+            std::env::temp_dir().join(format!("flowfine-rebase-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = |filename: &str| directory.join(filename).to_string_lossy().to_string();
+        fs::write(path("V1__a.cql"), "").unwrap();
+        fs::write(path("V2__b.cql"), "").unwrap();
+        let plan = RebasePlan {
+            renames: vec![VersionRename {
+                version: "1".to_string(),
+                new_version: "2".to_string(),
+                files: vec![FileRename {
+                    from: path("V1__a.cql"),
+                    to: path("V2__b.cql"),
+                }],
+            }],
+        };
+
+        // when
+        let result = plan.apply();
+        fs::remove_dir_all(&directory).unwrap();
+
+        // then
+        assert!(matches!(result, Err(RenameError(..))));
+    }
+}
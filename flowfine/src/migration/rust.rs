@@ -0,0 +1,49 @@
+use crate::config::{FilenameConvention, VersionFormatting};
+use crate::migration::version::MigrationVersionKey;
+use crate::migration::MigrationParsingError::InvalidVersionFormatError;
+use crate::migration::{Migration, MigrationKind, MigrationParsingError};
+use async_trait::async_trait;
+use scylla::Session;
+use std::error::Error;
+
+pub type RustMigrationError = Box<dyn Error + Send + Sync>;
+
+/// Versioned migration written in Rust, for changes CQL cannot express such as re-encoding
+/// blobs or backfilling from an external file. It is ordered and recorded in history like
+/// file-based migrations, under the name `V<version>__<description>.rs`.
+#[async_trait]
+pub trait RustMigration: Send + Sync {
+    fn version(&self) -> &str;
+
+    fn description(&self) -> &str;
+
+    /// Recorded in history and compared by validation, changes to the code go unnoticed without it.
+    fn checksum(&self) -> Option<String> {
+        None
+    }
+
+    async fn migrate(&self, session: &Session) -> Result<(), RustMigrationError>;
+}
+
+/// Describes `rust_migration` as a migration without statements, its checksum as content.
+pub fn to_migration(
+    rust_migration: &dyn RustMigration,
+    version_formatting: &VersionFormatting,
+    filename_convention: &FilenameConvention,
+) -> Result<Migration, MigrationParsingError> {
+    let version = rust_migration.version();
+    let description = rust_migration.description();
+    let filename = filename_convention.rust_filename(version, description);
+    let version_key = MigrationVersionKey::new(version_formatting, version)
+        .ok_or(InvalidVersionFormatError(filename.clone()))?;
+
+    Ok(Migration {
+        filename,
+        kind: MigrationKind::Versioned,
+        version: version.to_string(),
+        version_key: Some(version_key),
+        name: description.to_string(),
+        content: rust_migration.checksum().unwrap_or_default(),
+        queries: Vec::new(),
+    })
+}
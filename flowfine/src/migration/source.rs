@@ -0,0 +1,522 @@
+use crate::config::{FilenameConvention, UnrecognizedFiles, VersionFormatting};
+use crate::migration::FileError;
+use crate::migration::FileError::*;
+use glob::Pattern;
+use std::fs::{read, read_dir, read_to_string};
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MigrationFile {
+    /// Location of the file within its source, e.g. `migrations/billing/V1__create_invoices.cql`.
+    pub path: String,
+    pub filename: String,
+    pub content: String,
+}
+
+impl MigrationFile {
+    /// Names the file after the last component of `path`.
+    pub fn new(path: &str, content: &str) -> Self {
+        let filename = path.rsplit(['/', '\\']).next().unwrap_or(path);
+
+        Self {
+            path: path.to_string(),
+            filename: filename.to_string(),
+            content: content.to_string(),
+        }
+    }
+}
+
+/// Yields the files a migration set is parsed from; every file is expected to be a
+/// migration or a callback script.
+pub trait MigrationSource: Send + Sync {
+    fn files(&self) -> Result<Vec<MigrationFile>, FileError>;
+
+    /// Formatting the files were written for, overriding `MigrationConfig::version_formatting`.
+    fn version_formatting(&self) -> Option<VersionFormatting> {
+        None
+    }
+
+    /// Overrides `MigrationConfig::filename_convention`.
+    fn filename_convention(&self) -> Option<FilenameConvention> {
+        None
+    }
+
+    /// Overrides `MigrationConfig::unrecognized_files`.
+    fn unrecognized_files(&self) -> Option<UnrecognizedFiles> {
+        None
+    }
+}
+
+/// Name of the file listing, one glob per line, the files a directory source skips.
+pub const IGNORE_FILENAME: &str = ".flowfineignore";
+
+/// Files of a directory and all of its subdirectories, except those matching an ignore
+/// pattern or one of the `.flowfineignore` file at the root of the directory.
+///
+/// Only files with one of the `FilenameConvention::suffixes` are read, others are listed
+/// without content for the parser to report or skip as unrecognized.
+#[derive(Clone, Debug)]
+pub struct DirectorySource {
+    path: PathBuf,
+    ignore_patterns: Vec<String>,
+    filename_convention: FilenameConvention,
+}
+
+impl DirectorySource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            ignore_patterns: Vec::new(),
+            filename_convention: FilenameConvention::default(),
+        }
+    }
+
+    /// Reads the files with one of the suffixes of `filename_convention` instead of `.cql`.
+    pub fn with_filename_convention(mut self, filename_convention: &FilenameConvention) -> Self {
+        self.filename_convention = filename_convention.clone();
+        self
+    }
+
+    /// Patterns containing a `/` match the path relative to the directory, others the name
+    /// of a file or subdirectory at any depth.
+    pub fn with_ignore_patterns(mut self, patterns: &[String]) -> Self {
+        self.ignore_patterns.extend(patterns.iter().cloned());
+        self
+    }
+}
+
+impl MigrationSource for DirectorySource {
+    fn files(&self) -> Result<Vec<MigrationFile>, FileError> {
+        let ignore_file = read_to_string(self.path.join(IGNORE_FILENAME)).unwrap_or_default();
+        let ignore = IgnoreMatcher {
+            root: &self.path,
+            patterns: compile_ignore_patterns(&self.ignore_patterns, &ignore_file)?,
+        };
+
+        let mut files = Vec::new();
+        read_directory(&self.path, &ignore, &self.filename_convention, &mut files)?;
+
+        Ok(files)
+    }
+}
+
+/// Compiles `patterns` along with the non-comment lines of an ignore file's `content`.
+fn compile_ignore_patterns(patterns: &[String], content: &str) -> Result<Vec<Pattern>, FileError> {
+    let file_patterns = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string);
+
+    patterns
+        .iter()
+        .cloned()
+        .chain(file_patterns)
+        .map(|pattern| {
+            Pattern::new(&pattern).map_err(|err| InvalidIgnorePatternError(pattern, err))
+        })
+        .collect()
+}
+
+/// Decodes the content of `path` when its name has one of the suffixes of `filename_convention`,
+/// leaving it empty otherwise so unrecognized binaries such as `.DS_Store` do not fail loading.
+fn migration_file(
+    path: &str,
+    filename_convention: &FilenameConvention,
+    read_content: impl FnOnce() -> io::Result<Vec<u8>>,
+) -> Result<MigrationFile, FileError> {
+    let file = MigrationFile::new(path, "");
+    if filename_convention.suffix(&file.filename).is_none() {
+        return Ok(file);
+    }
+
+    let content = read_content()
+        .and_then(|bytes| {
+            String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        })
+        .map_err(|err| FileNotReadError(path.to_string(), err))?;
+
+    Ok(MigrationFile { content, ..file })
+}
+
+struct IgnoreMatcher<'a> {
+    root: &'a Path,
+    patterns: Vec<Pattern>,
+}
+
+impl IgnoreMatcher<'_> {
+    fn is_ignored(&self, path: &Path) -> bool {
+        let relative_path = path.strip_prefix(self.root).unwrap_or(path);
+        if relative_path == Path::new(IGNORE_FILENAME) {
+            return true;
+        }
+
+        let relative_path = relative_path.to_string_lossy().replace('\\', "/");
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+
+        self.patterns
+            .iter()
+            .any(|pattern| match pattern.as_str().contains('/') {
+                true => pattern.matches(&relative_path),
+                false => pattern.matches(&name),
+            })
+    }
+}
+
+fn read_directory(
+    path: &Path,
+    ignore: &IgnoreMatcher,
+    filename_convention: &FilenameConvention,
+    files: &mut Vec<MigrationFile>,
+) -> Result<(), FileError> {
+    let directory = path.display().to_string();
+    let entries = read_dir(path).map_err(|err| DirectoryNotLoadedError(directory.clone(), err))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|err| FileNotLoadedError(directory.clone(), err))?;
+        let path = entry.path();
+
+        if ignore.is_ignored(&path) {
+            continue;
+        }
+
+        if path.is_dir() {
+            read_directory(&path, ignore, filename_convention, files)?;
+        } else {
+            files.push(migration_file(
+                &path.to_string_lossy(),
+                filename_convention,
+                || read(&path),
+            )?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Files built in code, e.g. by tests or tools generating migrations.
+#[derive(Clone, Debug, Default)]
+pub struct InMemorySource {
+    files: Vec<MigrationFile>,
+}
+
+impl InMemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_file(mut self, filename: &str, content: &str) -> Self {
+        self.files.push(MigrationFile::new(filename, content));
+        self
+    }
+}
+
+impl MigrationSource for InMemorySource {
+    fn files(&self) -> Result<Vec<MigrationFile>, FileError> {
+        Ok(self.files.clone())
+    }
+}
+
+/// Concatenates the files of several sources, e.g. one `DirectorySource` per location.
+impl<S: MigrationSource> MigrationSource for Vec<S> {
+    fn files(&self) -> Result<Vec<MigrationFile>, FileError> {
+        let mut files = Vec::new();
+        for source in self {
+            files.extend(source.files()?);
+        }
+
+        Ok(files)
+    }
+}
+
+/// Regular files of a `.tar`, `.tar.gz` or `.tgz` archive at any depth, skipped and read like
+/// those of a `DirectorySource`, a `.flowfineignore` being looked up at the archive root.
+#[cfg(feature = "archive")]
+#[derive(Clone, Debug)]
+pub struct ArchiveSource {
+    path: PathBuf,
+    ignore_patterns: Vec<String>,
+    filename_convention: FilenameConvention,
+}
+
+#[cfg(feature = "archive")]
+impl ArchiveSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            ignore_patterns: Vec::new(),
+            filename_convention: FilenameConvention::default(),
+        }
+    }
+
+    /// See `DirectorySource::with_ignore_patterns`, paths being relative to the archive root.
+    pub fn with_ignore_patterns(mut self, patterns: &[String]) -> Self {
+        self.ignore_patterns.extend(patterns.iter().cloned());
+        self
+    }
+
+    pub fn with_filename_convention(mut self, filename_convention: &FilenameConvention) -> Self {
+        self.filename_convention = filename_convention.clone();
+        self
+    }
+
+    fn is_compressed(&self) -> bool {
+        let path = self.path.to_string_lossy();
+        path.ends_with(".tar.gz") || path.ends_with(".tgz")
+    }
+
+    /// Paths, relative to the archive root, and raw contents of the regular files.
+    fn read_entries(&self, reader: impl io::Read) -> io::Result<Vec<(PathBuf, Vec<u8>)>> {
+        use std::io::Read;
+
+        let mut archive = tar::Archive::new(reader);
+        let mut entries = Vec::new();
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let path = entry.path()?;
+            let path = path.strip_prefix(".").unwrap_or(&path).to_path_buf();
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+
+            entries.push((path, content));
+        }
+
+        Ok(entries)
+    }
+}
+
+#[cfg(feature = "archive")]
+impl MigrationSource for ArchiveSource {
+    fn files(&self) -> Result<Vec<MigrationFile>, FileError> {
+        let archive_error = |err| ArchiveNotLoadedError(self.path.display().to_string(), err);
+        let file = std::fs::File::open(&self.path).map_err(archive_error)?;
+        let entries = match self.is_compressed() {
+            true => self.read_entries(flate2::read::GzDecoder::new(file)),
+            false => self.read_entries(file),
+        }
+        .map_err(archive_error)?;
+
+        let ignore_file = entries
+            .iter()
+            .find(|(path, _)| path == Path::new(IGNORE_FILENAME))
+            .map(|(_, content)| String::from_utf8_lossy(content).to_string())
+            .unwrap_or_default();
+        let ignore = IgnoreMatcher {
+            root: Path::new(""),
+            patterns: compile_ignore_patterns(&self.ignore_patterns, &ignore_file)?,
+        };
+
+        entries
+            .into_iter()
+            // a directory source skips ignored subdirectories with all of their files
+            .filter(|(path, _)| {
+                !path
+                    .ancestors()
+                    .filter(|ancestor| !ancestor.as_os_str().is_empty())
+                    .any(|ancestor| ignore.is_ignored(ancestor))
+            })
+            .map(|(path, content)| {
+                migration_file(&path.to_string_lossy(), &self.filename_convention, || {
+                    Ok(content)
+                })
+                .map_err(|err| match err {
+                    FileNotReadError(path, err) => {
+                        FileNotReadError(format!("{} of {}", path, self.path.display()), err)
+                    }
+                    err => err,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_nested_directory_files() {
+        // given
+        let source = DirectorySource::new("./tests/data/unit/nested_migrations");
+
+        // when
+        let mut paths = source
+            .files()
+            .unwrap()
+            .into_iter()
+            .map(|file| (file.path, file.filename))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        // then
+        assert_eq!(
+            paths,
+            vec![
+                (
+                    "./tests/data/unit/nested_migrations/V1__create_foo.cql".to_string(),
+                    "V1__create_foo.cql".to_string()
+                ),
+                (
+                    "./tests/data/unit/nested_migrations/billing/V2__create_invoices.cql"
+                        .to_string(),
+                    "V2__create_invoices.cql".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skip_ignored_files() {
+        // given
+        let source = DirectorySource::new("./tests/data/unit/ignored_migrations")
+            .with_ignore_patterns(&["*.txt".to_string()]);
+
+        // when
+        let files = source.files().unwrap();
+
+        // then
+        let filenames = files
+            .iter()
+            .map(|file| file.filename.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(filenames, vec!["V1__create_foo.cql"]);
+    }
+
+    #[test]
+    fn test_list_unrecognized_binary_files_without_content() {
+        // given
+        let source = DirectorySource::new("./tests/data/unit/binary_migrations");
+
+        // when
+        let mut files = source.files().unwrap();
+        files.sort_by(|a, b| a.filename.cmp(&b.filename));
+
+        // then
+        assert_eq!(files[0].filename, ".DS_Store");
+        assert!(files[0].content.is_empty());
+        assert!(files[1].content.starts_with("CREATE TABLE foo"));
+    }
+
+    #[test]
+    fn test_report_migration_that_is_not_utf8() {
+        // given
+        let directory =
+            std::env::temp_dir().join(format!("flowfine-source-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("V1__binary.cql"), [0xff, 0xfe, b';']).unwrap();
+
+        // when
+        let result = DirectorySource::new(&directory).files();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        // then
+        match result {
+            Err(FileNotReadError(path, err)) => {
+                assert!(path.ends_with("V1__binary.cql"));
+                assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "archive")]
+    fn write_archive(name: &str, entries: &[(&str, &[u8])]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("flowfine-{}-{}", std::process::id(), name));
+        let encoder = flate2::write::GzEncoder::new(
+            std::fs::File::create(&path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        for (entry_path, content) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, entry_path, *content)
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+
+        path
+    }
+
+    #[cfg(feature = "archive")]
+    #[test]
+    fn test_read_compressed_archive() {
+        // given
+        let content = "CREATE TABLE foo (id int PRIMARY KEY);";
+        let path = write_archive(
+            "read.tar.gz",
+            &[("migrations/V1__create_foo.cql", content.as_bytes())],
+        );
+
+        // when
+        let files = ArchiveSource::new(&path).files();
+        std::fs::remove_file(&path).unwrap();
+
+        // then
+        assert_eq!(
+            files.unwrap(),
+            vec![MigrationFile::new("migrations/V1__create_foo.cql", content)]
+        );
+    }
+
+    #[cfg(feature = "archive")]
+    #[test]
+    fn test_skip_ignored_archive_entries() {
+        // given
+        let content = "CREATE TABLE foo (id int PRIMARY KEY);";
+        let path = write_archive(
+            "ignore.tar.gz",
+            &[
+                (".flowfineignore", b"*.md\ndrafts\n"),
+                ("README.md", b"# migrations"),
+                ("drafts/V2__draft.cql", b"\xff"),
+                (".DS_Store", b"\x00\x00\x00\x01Bud1\xff"),
+                ("V1__create_foo.cql", content.as_bytes()),
+            ],
+        );
+
+        // when
+        let files = ArchiveSource::new(&path)
+            .with_ignore_patterns(&["*.txt".to_string()])
+            .files();
+        std::fs::remove_file(&path).unwrap();
+
+        // then
+        assert_eq!(
+            files.unwrap(),
+            vec![
+                MigrationFile::new(".DS_Store", ""),
+                MigrationFile::new("V1__create_foo.cql", content),
+            ]
+        );
+    }
+
+    #[cfg(feature = "archive")]
+    #[test]
+    fn test_report_archive_entry_that_is_not_utf8() {
+        // given
+        let path = write_archive("binary.tar.gz", &[("V1__binary.cql", b"\xff\xfe;")]);
+
+        // when
+        let result = ArchiveSource::new(&path).files();
+        std::fs::remove_file(&path).unwrap();
+
+        // then
+        assert!(matches!(
+            result,
+            Err(FileNotReadError(path, _)) if path.starts_with("V1__binary.cql of ")
+        ));
+    }
+}
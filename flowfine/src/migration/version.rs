@@ -1,54 +1,253 @@
-use crate::config::VersionFormatting;
+use crate::config::{CustomVersionFormat, VersionFormatting};
 
-use chrono::NaiveDateTime;
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
 use lazy_static::lazy_static;
 use regex::Regex;
+use std::cmp::Ordering;
 
 lazy_static! {
     static ref NUMERIC_VERSION_REGEX: Regex =
         Regex::new(r"^(\d{1,10}(\.\d+)*|\d+(\.\d+)+)$").unwrap();
     static ref DATETIME_VERSION_FORMAT: String = "%Y%m%d%H%M%S".to_string();
+    static ref SEMVER_VERSION_REGEX: Regex = Regex::new(
+        r"^(0|[1-9]\d*)\.(0|[1-9]\d*)\.(0|[1-9]\d*)(?:-([0-9A-Za-z-]+(?:\.[0-9A-Za-z-]+)*))?$"
+    )
+    .unwrap();
+    static ref EPOCH_VERSION_REGEX: Regex = Regex::new(r"^\d{1,18}$").unwrap();
 }
 
 #[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Ord)]
 pub enum MigrationVersionKey {
-    Numeric(String),
+    /// Dot-separated parts compared by value, so `9` comes before `10`.
+    Numeric(Vec<u64>),
     Datetime(NaiveDateTime),
+    Semver(SemanticVersion),
+    Epoch(i64),
+    /// Capture groups of a `CustomVersionFormat::Pattern`, unmatched optional groups first.
+    Captures(Vec<Option<VersionPart>>),
+}
+
+/// `MAJOR.MINOR.PATCH[-PRE-RELEASE]`, a pre-release ordered before its release.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SemanticVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre_release: Vec<VersionPart>,
+}
+
+/// Numeric parts are compared by value and ordered before textual ones.
+#[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Ord)]
+pub enum VersionPart {
+    Number(u64),
+    Text(String),
+}
+
+impl Ord for SemanticVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(
+                || match (self.pre_release.is_empty(), other.pre_release.is_empty()) {
+                    (true, true) => Ordering::Equal,
+                    (true, false) => Ordering::Greater,
+                    (false, true) => Ordering::Less,
+                    (false, false) => self.pre_release.cmp(&other.pre_release),
+                },
+            )
+    }
+}
+
+impl PartialOrd for SemanticVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl VersionPart {
+    fn parse(part: &str) -> Self {
+        match part.bytes().all(|byte| byte.is_ascii_digit()) {
+            true => part
+                .parse()
+                .map(VersionPart::Number)
+                .unwrap_or_else(|_| VersionPart::Text(part.to_string())),
+            false => VersionPart::Text(part.to_string()),
+        }
+    }
 }
 
 impl MigrationVersionKey {
     pub fn new(version_formatting: &VersionFormatting, version: &str) -> Option<Self> {
         match version_formatting {
             VersionFormatting::Numeric => Self::parse_numeric_version(version),
-            VersionFormatting::Datetime => Self::parse_datetime_version(version),
+            VersionFormatting::Datetime => {
+                Self::parse_datetime_version(version, &DATETIME_VERSION_FORMAT)
+            }
+            VersionFormatting::Semver => Self::parse_semver_version(version),
+            VersionFormatting::Epoch => Self::parse_epoch_version(version),
+            VersionFormatting::Custom(CustomVersionFormat::Datetime(format)) => {
+                Self::parse_datetime_version(version, format)
+            }
+            VersionFormatting::Custom(CustomVersionFormat::Pattern(pattern)) => {
+                Self::parse_pattern_version(version, pattern.regex())
+            }
         }
     }
 
     fn parse_numeric_version(version: &str) -> Option<MigrationVersionKey> {
-        if NUMERIC_VERSION_REGEX.is_match(version) {
-            Some(MigrationVersionKey::Numeric(version.to_string()))
-        } else {
-            None
+        if !NUMERIC_VERSION_REGEX.is_match(version) {
+            return None;
         }
+
+        version
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect::<Option<Vec<_>>>()
+            .map(MigrationVersionKey::Numeric)
+    }
+
+    /// Formats without a time of day are parsed as midnight.
+    fn parse_datetime_version(version: &str, format: &str) -> Option<MigrationVersionKey> {
+        NaiveDateTime::parse_from_str(version, format)
+            .ok()
+            .or_else(|| {
+                NaiveDate::parse_from_str(version, format)
+                    .ok()?
+                    .and_hms_opt(0, 0, 0)
+            })
+            .map(MigrationVersionKey::Datetime)
     }
 
-    fn parse_datetime_version(version: &str) -> Option<MigrationVersionKey> {
-        match NaiveDateTime::parse_from_str(version, &DATETIME_VERSION_FORMAT) {
-            Ok(datetime) => Some(MigrationVersionKey::Datetime(datetime)),
-            Err(_) => None,
+    fn parse_semver_version(version: &str) -> Option<MigrationVersionKey> {
+        let captures = SEMVER_VERSION_REGEX.captures(version)?;
+        let number = |index: usize| captures[index].parse::<u64>().ok();
+
+        Some(MigrationVersionKey::Semver(SemanticVersion {
+            major: number(1)?,
+            minor: number(2)?,
+            patch: number(3)?,
+            pre_release: captures
+                .get(4)
+                .map(|pre_release| {
+                    pre_release
+                        .as_str()
+                        .split('.')
+                        .map(VersionPart::parse)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }))
+    }
+
+    fn parse_epoch_version(version: &str) -> Option<MigrationVersionKey> {
+        match EPOCH_VERSION_REGEX.is_match(version) {
+            true => version.parse().ok().map(MigrationVersionKey::Epoch),
+            false => None,
         }
     }
+
+    /// `pattern` has to match the whole version.
+    fn parse_pattern_version(version: &str, pattern: &Regex) -> Option<MigrationVersionKey> {
+        let captures = pattern.captures(version)?;
+        let whole_match = captures.get(0)?;
+        if whole_match.start() != 0 || whole_match.end() != version.len() {
+            return None;
+        }
+
+        Some(MigrationVersionKey::Captures(
+            captures
+                .iter()
+                .skip(1)
+                .map(|group| group.map(|group| VersionPart::parse(group.as_str())))
+                .collect(),
+        ))
+    }
+}
+
+/// Orders two versions by their keys, falling back to comparing them as text when one of them
+/// does not match `version_formatting`.
+pub fn compare_versions(version_formatting: &VersionFormatting, a: &str, b: &str) -> Ordering {
+    match (
+        MigrationVersionKey::new(version_formatting, a),
+        MigrationVersionKey::new(version_formatting, b),
+    ) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/// Version for a new migration: the current time, or the major version after the latest one.
+/// Versions of a custom pattern cannot be derived.
+pub fn next_version<'a>(
+    version_formatting: &VersionFormatting,
+    versions: impl Iterator<Item = &'a str>,
+) -> Option<String> {
+    match version_formatting {
+        VersionFormatting::Numeric => {
+            let latest_major = versions
+                .filter_map(|version| version.split('.').next()?.parse::<u64>().ok())
+                .max()
+                .unwrap_or(0);
+            Some((latest_major + 1).to_string())
+        }
+        VersionFormatting::Datetime => {
+            Some(Utc::now().format(&DATETIME_VERSION_FORMAT).to_string())
+        }
+        VersionFormatting::Semver => {
+            let latest_major = versions
+                .filter_map(
+                    |version| match MigrationVersionKey::parse_semver_version(version)? {
+                        MigrationVersionKey::Semver(semver) => Some(semver.major),
+                        _ => None,
+                    },
+                )
+                .max();
+            Some(format!("{}.0.0", latest_major.map_or(1, |major| major + 1)))
+        }
+        VersionFormatting::Epoch => Some(Utc::now().timestamp().to_string()),
+        VersionFormatting::Custom(CustomVersionFormat::Datetime(format)) => {
+            Some(Utc::now().format(format).to_string())
+        }
+        VersionFormatting::Custom(CustomVersionFormat::Pattern(_)) => None,
+    }
+}
+
+/// Closest version following `version`, e.g. the next major version or one second later.
+/// Versions of a custom pattern have none.
+pub fn version_after(version_formatting: &VersionFormatting, version: &str) -> Option<String> {
+    let format = match version_formatting {
+        VersionFormatting::Custom(CustomVersionFormat::Datetime(format)) => format.as_str(),
+        _ => DATETIME_VERSION_FORMAT.as_str(),
+    };
+
+    match MigrationVersionKey::new(version_formatting, version)? {
+        MigrationVersionKey::Numeric(_) | MigrationVersionKey::Semver(_) => {
+            next_version(version_formatting, [version].into_iter())
+        }
+        MigrationVersionKey::Epoch(seconds) => Some((seconds + 1).to_string()),
+        // the smallest step depends on the fields of the format
+        MigrationVersionKey::Datetime(datetime) => [1, 60, 60 * 60, 24 * 60 * 60]
+            .into_iter()
+            .map(|seconds| {
+                (datetime + Duration::seconds(seconds))
+                    .format(format)
+                    .to_string()
+            })
+            .find(|candidate| compare_versions(version_formatting, version, candidate).is_lt()),
+        MigrationVersionKey::Captures(_) => None,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::VersionPattern;
     use rstest::rstest;
 
     #[rstest(version, expected_result,
-    case("1", MigrationVersionKey::Numeric("1".to_string())),
-    case("1.2", MigrationVersionKey::Numeric("1.2".to_string())),
-    case("1.2.3",MigrationVersionKey::Numeric("1.2.3".to_string())),
+    case("1", MigrationVersionKey::Numeric(vec![1])),
+    case("1.2", MigrationVersionKey::Numeric(vec![1, 2])),
+    case("1.2.3",MigrationVersionKey::Numeric(vec![1, 2, 3])),
     )]
     fn test_valid_numeric_version(version: &str, expected_result: MigrationVersionKey) {
         // when
@@ -95,4 +294,177 @@ mod tests {
         // then
         assert!(migrated_version.is_none());
     }
+
+    #[rstest(versions, expected_result,
+    case(vec![], "1"),
+    case(vec!["1", "1.1", "2.0"], "3"),
+    case(vec!["9", "10"], "11"),
+    )]
+    fn test_next_numeric_version(versions: Vec<&str>, expected_result: &str) {
+        // when
+        let version = next_version(&VersionFormatting::Numeric, versions.into_iter());
+
+        // then
+        assert_eq!(version.as_deref(), Some(expected_result));
+    }
+
+    #[test]
+    fn test_order_semver_versions() {
+        // given
+        let versions = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "1.2.0",
+            "1.10.0",
+            "2.0.0",
+        ];
+
+        // when
+        let keys = versions
+            .iter()
+            .map(|version| MigrationVersionKey::new(&VersionFormatting::Semver, version).unwrap())
+            .collect::<Vec<_>>();
+
+        // then
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[rstest(
+        version,
+        case("1"),
+        case("1.2"),
+        case("01.2.3"),
+        case("1.2.3-"),
+        case("1.2.3+build")
+    )]
+    fn test_invalid_semver_version(version: &str) {
+        // when
+        let migrated_version = MigrationVersionKey::new(&VersionFormatting::Semver, version);
+
+        // then
+        assert!(migrated_version.is_none());
+    }
+
+    #[rstest(
+        version,
+        expected_result,
+        case("1693742400", Some(MigrationVersionKey::Epoch(1693742400))),
+        case("-1693742400", None),
+        case("2023-09-03", None)
+    )]
+    fn test_epoch_version(version: &str, expected_result: Option<MigrationVersionKey>) {
+        // when
+        let migrated_version = MigrationVersionKey::new(&VersionFormatting::Epoch, version);
+
+        // then
+        assert_eq!(migrated_version, expected_result);
+    }
+
+    #[rstest(
+        format,
+        version,
+        expected_result,
+        case("%Y_%m_%d_%H%M", "2023_09_03_1200", Some("2023-09-03T12:00:00")),
+        case("%Y.%m.%d", "2023.09.03", Some("2023-09-03T00:00:00")),
+        case("%Y.%m.%d", "2023.13.03", None)
+    )]
+    fn test_custom_datetime_version(format: &str, version: &str, expected_result: Option<&str>) {
+        // given
+        let version_formatting =
+            VersionFormatting::Custom(CustomVersionFormat::Datetime(format.to_string()));
+
+        // when
+        let migrated_version = MigrationVersionKey::new(&version_formatting, version);
+
+        // then
+        assert_eq!(
+            migrated_version,
+            expected_result
+                .map(|datetime| MigrationVersionKey::Datetime(datetime.parse().unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_order_custom_pattern_versions() {
+        // given
+        let version_formatting = VersionFormatting::Custom(CustomVersionFormat::Pattern(
+            VersionPattern::new(r"(\d+)-([a-z]+)(?:-(\d+))?").unwrap(),
+        ));
+        let versions = ["2-alpha", "2-alpha-3", "2-alpha-10", "2-beta", "10-alpha"];
+
+        // when
+        let keys = versions
+            .iter()
+            .map(|version| MigrationVersionKey::new(&version_formatting, version).unwrap())
+            .collect::<Vec<_>>();
+
+        // then
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(MigrationVersionKey::new(&version_formatting, "x2-alpha").is_none());
+    }
+
+    #[rstest(
+        version_formatting,
+        a,
+        b,
+        expected_result,
+        case(VersionFormatting::Numeric, "9", "10", Ordering::Less),
+        case(VersionFormatting::Numeric, "1.9", "1.10", Ordering::Less),
+        case(VersionFormatting::Numeric, "2", "10.1", Ordering::Less),
+        case(VersionFormatting::Semver, "1.9.0", "1.10.0", Ordering::Less),
+        case(VersionFormatting::Semver, "1.0.0", "1.0.0-rc.1", Ordering::Greater),
+        case(VersionFormatting::Epoch, "999999999", "1693742400", Ordering::Less)
+    )]
+    fn test_compare_versions(
+        version_formatting: VersionFormatting,
+        a: &str,
+        b: &str,
+        expected_result: Ordering,
+    ) {
+        assert_eq!(compare_versions(&version_formatting, a, b), expected_result);
+    }
+
+    #[rstest(version_formatting, version, expected_result,
+    case(VersionFormatting::Numeric, "1.4", Some("2")),
+    case(VersionFormatting::Numeric, "9", Some("10")),
+    case(VersionFormatting::Semver, "1.4.0-rc.1", Some("2.0.0")),
+    case(VersionFormatting::Datetime, "20231001120000", Some("20231001120001")),
+    case(VersionFormatting::Epoch, "1693742400", Some("1693742401")),
+    case(VersionFormatting::Custom(CustomVersionFormat::Datetime("%Y_%m_%d".to_string())), "2023_10_01", Some("2023_10_02")),
+    case(VersionFormatting::Custom(CustomVersionFormat::Pattern(VersionPattern::new(r"(\d+)").unwrap())), "1", None),
+    )]
+    fn test_version_after(
+        version_formatting: VersionFormatting,
+        version: &str,
+        expected_result: Option<&str>,
+    ) {
+        // when
+        let next = version_after(&version_formatting, version);
+
+        // then
+        assert_eq!(next.as_deref(), expected_result);
+    }
+
+    #[rstest(version_formatting, versions, expected_result,
+    case(VersionFormatting::Semver, vec![], Some("1.0.0")),
+    case(VersionFormatting::Semver, vec!["1.2.0", "2.0.0-rc.1", "1.10.0"], Some("3.0.0")),
+    case(VersionFormatting::Custom(CustomVersionFormat::Pattern(VersionPattern::new(r"(\d+)").unwrap())), vec!["1"], None),
+    )]
+    fn test_next_version(
+        version_formatting: VersionFormatting,
+        versions: Vec<&str>,
+        expected_result: Option<&str>,
+    ) {
+        // when
+        let version = next_version(&version_formatting, versions.into_iter());
+
+        // then
+        assert_eq!(version.as_deref(), expected_result);
+    }
 }
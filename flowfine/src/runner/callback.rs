@@ -0,0 +1,141 @@
+use crate::migration::{CallbackEvent, CallbackScript, Migration};
+use crate::runner::{AppliedMigration, MigrationExecutionError};
+use async_trait::async_trait;
+use scylla::Session;
+use std::error::Error;
+
+pub type CallbackError = Box<dyn Error + Send + Sync>;
+
+/// Hooks invoked by the runner at each point of a migration run.
+///
+/// Every hook has a no-op default, so implementors only override the events they care about.
+/// Returning an error from a hook aborts the run with `MigrationExecutionError::CallbackError`.
+#[async_trait]
+pub trait MigrationCallback: Send + Sync {
+    async fn before_migrate(
+        &self,
+        _session: &Session,
+        _migrations: &[Migration],
+    ) -> Result<(), CallbackError> {
+        Ok(())
+    }
+
+    async fn before_each(
+        &self,
+        _session: &Session,
+        _migration: &Migration,
+    ) -> Result<(), CallbackError> {
+        Ok(())
+    }
+
+    async fn after_each(
+        &self,
+        _session: &Session,
+        _migration: &Migration,
+        _applied_migration: &AppliedMigration,
+    ) -> Result<(), CallbackError> {
+        Ok(())
+    }
+
+    async fn after_each_error(
+        &self,
+        _session: &Session,
+        _migration: &Migration,
+        _error: &MigrationExecutionError,
+    ) -> Result<(), CallbackError> {
+        Ok(())
+    }
+
+    async fn after_migrate(
+        &self,
+        _session: &Session,
+        _applied_migrations: &[AppliedMigration],
+    ) -> Result<(), CallbackError> {
+        Ok(())
+    }
+
+    async fn after_validate_error(
+        &self,
+        _session: &Session,
+        _error: &MigrationExecutionError,
+    ) -> Result<(), CallbackError> {
+        Ok(())
+    }
+}
+
+/// Runs the CQL callback files found next to the migrations, e.g. `beforeMigrate.cql`.
+pub struct SqlCallbacks {
+    scripts: Vec<CallbackScript>,
+}
+
+impl SqlCallbacks {
+    pub fn new(scripts: Vec<CallbackScript>) -> Self {
+        Self { scripts }
+    }
+
+    async fn execute(&self, session: &Session, event: CallbackEvent) -> Result<(), CallbackError> {
+        let scripts = self.scripts.iter().filter(|script| script.event == event);
+
+        for script in scripts {
+            for query in &script.queries {
+                session.query(query.clone(), &[]).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MigrationCallback for SqlCallbacks {
+    async fn before_migrate(
+        &self,
+        session: &Session,
+        _migrations: &[Migration],
+    ) -> Result<(), CallbackError> {
+        self.execute(session, CallbackEvent::BeforeMigrate).await
+    }
+
+    async fn before_each(
+        &self,
+        session: &Session,
+        _migration: &Migration,
+    ) -> Result<(), CallbackError> {
+        self.execute(session, CallbackEvent::BeforeEach).await
+    }
+
+    async fn after_each(
+        &self,
+        session: &Session,
+        _migration: &Migration,
+        _applied_migration: &AppliedMigration,
+    ) -> Result<(), CallbackError> {
+        self.execute(session, CallbackEvent::AfterEach).await
+    }
+
+    async fn after_each_error(
+        &self,
+        session: &Session,
+        _migration: &Migration,
+        _error: &MigrationExecutionError,
+    ) -> Result<(), CallbackError> {
+        self.execute(session, CallbackEvent::AfterEachError).await
+    }
+
+    async fn after_migrate(
+        &self,
+        session: &Session,
+        _applied_migrations: &[AppliedMigration],
+    ) -> Result<(), CallbackError> {
+        self.execute(session, CallbackEvent::AfterMigrate).await
+    }
+
+    async fn after_validate_error(
+        &self,
+        session: &Session,
+        _error: &MigrationExecutionError,
+    ) -> Result<(), CallbackError> {
+        self.execute(session, CallbackEvent::AfterValidateError)
+            .await
+    }
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+/// Progress of a migration run, passed to the configured `EventSink`.
+///
+/// There is no lock acquired event because the runner does not lock the keyspace, concurrent
+/// runs are not serialized.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MigrationEvent {
+    RunStarted {
+        keyspace: String,
+    },
+    ReplicationMismatch {
+        keyspace: String,
+        expected: String,
+        actual: String,
+    },
+    MigrationStarted {
+        version: String,
+        filename: String,
+    },
+    StatementExecuted {
+        version: String,
+        index: usize,
+        duration: Duration,
+    },
+    MigrationFinished {
+        version: String,
+        filename: String,
+        duration: Duration,
+    },
+    MigrationFailed {
+        version: String,
+        filename: String,
+        error: String,
+    },
+    /// Recording a failed migration or running its `after_each_error` callbacks failed as well.
+    FailureHandlingFailed {
+        version: String,
+        filename: String,
+        error: String,
+    },
+    /// The run was cancelled before all statements of the migration were executed.
+    MigrationInterrupted {
+        version: String,
+        filename: String,
+    },
+    RunFinished {
+        applied: usize,
+        duration: Duration,
+    },
+    /// A file of a migration location was skipped, see `UnrecognizedFiles::Warn`.
+    UnrecognizedFile {
+        path: String,
+    },
+}
+
+/// Receives every event emitted by the runner, e.g. to forward it to a structured logger.
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: &MigrationEvent);
+}
+
+/// Prints a human-readable line for each applied or failed migration.
+pub struct StdoutSink;
+
+impl EventSink for StdoutSink {
+    fn emit(&self, event: &MigrationEvent) {
+        match event {
+            MigrationEvent::MigrationFinished { filename, .. } => {
+                println!("Applied migration {}", filename)
+            }
+            MigrationEvent::MigrationFailed {
+                filename, error, ..
+            } => println!("Failed to apply migration {}: {}", filename, error),
+            MigrationEvent::FailureHandlingFailed {
+                filename, error, ..
+            } => println!(
+                "Warning: failed to record the failure of migration {}: {}",
+                filename, error
+            ),
+            MigrationEvent::MigrationInterrupted { filename, .. } => {
+                println!("Interrupted migration {}", filename)
+            }
+            MigrationEvent::ReplicationMismatch {
+                keyspace,
+                expected,
+                actual,
+            } => println!(
+                "Warning: replication of keyspace {} is {} but {} is configured",
+                keyspace, actual, expected
+            ),
+            MigrationEvent::UnrecognizedFile { path } => {
+                println!("Warning: skipped unrecognized file {}", path)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Discards all events, leaving stdout untouched.
+pub struct NoopSink;
+
+impl EventSink for NoopSink {
+    fn emit(&self, _event: &MigrationEvent) {}
+}
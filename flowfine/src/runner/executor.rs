@@ -0,0 +1,242 @@
+use crate::runner::AppliedMigration;
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use scylla::cql_to_rust::FromRow;
+use scylla::frame::response::result::Row;
+use scylla::frame::value::Timestamp;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+lazy_static! {
+    static ref HISTORY_TABLE_NAME: String = "flowfine_history".to_string();
+    /// History columns in the order `AppliedMigration` decodes them.
+    static ref HISTORY_COLUMNS: Vec<&'static str> = vec![
+        "id",
+        "version",
+        "name",
+        "filename",
+        "checksum",
+        "applied_at",
+        "success",
+        "interrupted",
+        "execution_time",
+        "executed_by",
+        "statement_count",
+        "flowfine_version",
+    ];
+    /// Columns added to the history table after its first release, with their CQL types.
+    static ref UPGRADED_HISTORY_COLUMNS: Vec<(&'static str, &'static str)> = vec![
+        ("interrupted", "BOOLEAN"),
+        ("execution_time", "BIGINT"),
+        ("executed_by", "TEXT"),
+        ("statement_count", "INT"),
+        ("flowfine_version", "TEXT"),
+    ];
+}
+
+/// Database access used by the runner: statement execution, history and schema agreement.
+#[async_trait]
+pub trait MigrationExecutor: Send + Sync {
+    fn keyspace(&self) -> &str;
+
+    /// The underlying session, if the executor talks to a real cluster.
+    fn session(&self) -> Option<&Session>;
+
+    async fn execute(&self, statement: &str) -> Result<(), QueryError>;
+
+    async fn find_replication(&self) -> Result<Option<HashMap<String, String>>, QueryError>;
+
+    async fn create_history_table(&self) -> Result<(), QueryError>;
+
+    /// Returns successfully applied migrations, the most recent first, or nothing when the
+    /// history table does not exist.
+    async fn find_applied_migrations(&self) -> Result<Vec<AppliedMigration>, QueryError>;
+
+    /// Inserts a history row, overwriting the row with the same id if there is one.
+    async fn insert_history(&self, applied_migration: &AppliedMigration) -> Result<(), QueryError>;
+
+    async fn delete_failed_history(&self) -> Result<(), QueryError>;
+
+    async fn await_schema_agreement(&self) -> Result<(), QueryError>;
+}
+
+pub struct ScyllaExecutor {
+    session: Arc<Session>,
+    keyspace: String,
+}
+
+impl ScyllaExecutor {
+    pub fn new(session: Arc<Session>, keyspace: &str) -> Self {
+        Self {
+            session,
+            keyspace: keyspace.to_string(),
+        }
+    }
+
+    async fn find_history_columns(&self) -> Result<HashSet<String>, QueryError> {
+        let query = "SELECT column_name FROM system_schema.columns WHERE keyspace_name = ? AND table_name = ?;";
+
+        self.session
+            .query(query, (&self.keyspace, &*HISTORY_TABLE_NAME))
+            .await?
+            .rows_typed_or_empty::<(String,)>()
+            .map(|row| row.map(|(column,)| column))
+            .collect::<Result<HashSet<_>, _>>()
+            .map_err(|err| QueryError::InvalidMessage(err.to_string()))
+    }
+}
+
+#[async_trait]
+impl MigrationExecutor for ScyllaExecutor {
+    fn keyspace(&self) -> &str {
+        &self.keyspace
+    }
+
+    fn session(&self) -> Option<&Session> {
+        Some(&self.session)
+    }
+
+    async fn execute(&self, statement: &str) -> Result<(), QueryError> {
+        self.session.query(statement, &[]).await.map(|_| ())
+    }
+
+    async fn find_replication(&self) -> Result<Option<HashMap<String, String>>, QueryError> {
+        let query = "SELECT replication FROM system_schema.keyspaces WHERE keyspace_name = ?;";
+
+        self.session
+            .query(query, (&self.keyspace,))
+            .await?
+            .maybe_first_row_typed::<(HashMap<String, String>,)>()
+            .map(|row| row.map(|(replication,)| replication))
+            .map_err(|err| QueryError::InvalidMessage(err.to_string()))
+    }
+
+    async fn create_history_table(&self) -> Result<(), QueryError> {
+        let query = format!(
+            "CREATE TABLE IF NOT EXISTS {keyspace}.{history_table} (
+                id         TEXT,
+                version    TEXT,
+                name       TEXT,
+                filename   TEXT,
+                checksum   TEXT,
+                success    BOOLEAN,
+                interrupted BOOLEAN,
+                applied_at TIMESTAMP,
+                execution_time   BIGINT,
+                executed_by      TEXT,
+                statement_count  INT,
+                flowfine_version TEXT,
+                PRIMARY KEY (success, applied_at)
+            ) WITH CLUSTERING ORDER BY (applied_at DESC);
+            ",
+            keyspace = self.keyspace,
+            history_table = *HISTORY_TABLE_NAME
+        );
+        self.session.query(query, &[]).await?;
+
+        // Tables created by older releases lack the newer columns.
+        let existing_columns = self.find_history_columns().await?;
+        let missing_columns = UPGRADED_HISTORY_COLUMNS
+            .iter()
+            .filter(|(column, _)| !existing_columns.contains(*column))
+            .collect::<Vec<_>>();
+        for (column, column_type) in &missing_columns {
+            let query = format!(
+                "ALTER TABLE {keyspace}.{history_table} ADD {column} {column_type};",
+                keyspace = self.keyspace,
+                history_table = *HISTORY_TABLE_NAME
+            );
+            self.session.query(query, &[]).await?;
+        }
+
+        match missing_columns.is_empty() {
+            true => Ok(()),
+            false => self.session.await_schema_agreement().await,
+        }
+    }
+
+    /// Reads history without touching the schema: a missing keyspace or table is an empty
+    /// history, and columns not added yet decode as `None`.
+    async fn find_applied_migrations(&self) -> Result<Vec<AppliedMigration>, QueryError> {
+        let existing_columns = self.find_history_columns().await?;
+        if existing_columns.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let selected_columns = HISTORY_COLUMNS
+            .iter()
+            .filter(|column| existing_columns.contains(**column))
+            .copied()
+            .collect::<Vec<_>>();
+        let query = format!(
+            "SELECT {columns} FROM {keyspace}.{history_table} WHERE success = true;",
+            columns = selected_columns.join(", "),
+            keyspace = self.keyspace,
+            history_table = *HISTORY_TABLE_NAME
+        );
+
+        self.session
+            .query(query, &[])
+            .await?
+            .rows_or_empty()
+            .into_iter()
+            .map(|row| {
+                let mut values = row.columns.into_iter();
+                let columns = HISTORY_COLUMNS
+                    .iter()
+                    .map(|column| match existing_columns.contains(*column) {
+                        true => values.next().flatten(),
+                        false => None,
+                    })
+                    .collect();
+                AppliedMigration::from_row(Row { columns })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| QueryError::InvalidMessage(err.to_string()))
+    }
+
+    async fn insert_history(&self, applied_migration: &AppliedMigration) -> Result<(), QueryError> {
+        let query = format!(
+            "INSERT INTO {keyspace}.{history_table} (id, version, name, filename, checksum, applied_at, success, interrupted, execution_time, executed_by, statement_count, flowfine_version) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);",
+            keyspace = self.keyspace,
+            history_table = *HISTORY_TABLE_NAME
+        );
+
+        self.session
+            .query(
+                query,
+                (
+                    &applied_migration.id,
+                    &applied_migration.version,
+                    &applied_migration.name,
+                    &applied_migration.filename,
+                    &applied_migration.checksum,
+                    Timestamp(applied_migration.applied_at),
+                    &applied_migration.success,
+                    &applied_migration.interrupted,
+                    &applied_migration.execution_time,
+                    &applied_migration.executed_by,
+                    &applied_migration.statement_count,
+                    &applied_migration.flowfine_version,
+                ),
+            )
+            .await
+            .map(|_| ())
+    }
+
+    async fn delete_failed_history(&self) -> Result<(), QueryError> {
+        let query = format!(
+            "DELETE FROM {keyspace}.{history_table} WHERE success = false;",
+            keyspace = self.keyspace,
+            history_table = *HISTORY_TABLE_NAME
+        );
+
+        self.session.query(query, &[]).await.map(|_| ())
+    }
+
+    async fn await_schema_agreement(&self) -> Result<(), QueryError> {
+        self.session.await_schema_agreement().await
+    }
+}
@@ -0,0 +1,146 @@
+use crate::runner::executor::MigrationExecutor;
+use crate::runner::AppliedMigration;
+use async_trait::async_trait;
+use scylla::transport::errors::QueryError;
+use scylla::Session;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+/// Executor that keeps history in memory and records every executed statement,
+/// so the runner can be tested without a cluster.
+pub struct InMemoryExecutor {
+    keyspace: String,
+    state: Mutex<InMemoryState>,
+}
+
+#[derive(Default)]
+struct InMemoryState {
+    statements: Vec<String>,
+    history: Vec<AppliedMigration>,
+    replication: Option<HashMap<String, String>>,
+    failures: Vec<(String, QueryError)>,
+    history_failure: Option<QueryError>,
+}
+
+impl InMemoryExecutor {
+    pub fn new(keyspace: &str) -> Self {
+        Self {
+            keyspace: keyspace.to_string(),
+            state: Mutex::new(InMemoryState::default()),
+        }
+    }
+
+    pub fn with_replication(self, replication: HashMap<String, String>) -> Self {
+        self.state().replication = Some(replication);
+        self
+    }
+
+    pub fn with_history(self, history: Vec<AppliedMigration>) -> Self {
+        self.state().history = history;
+        self
+    }
+
+    /// Fails every executed statement that contains `fragment` with the given error.
+    pub fn fail_on(self, fragment: &str, error: QueryError) -> Self {
+        self.state().failures.push((fragment.to_string(), error));
+        self
+    }
+
+    /// Fails every history insert with the given error.
+    pub fn fail_history_inserts(self, error: QueryError) -> Self {
+        self.state().history_failure = Some(error);
+        self
+    }
+
+    pub fn executed_statements(&self) -> Vec<String> {
+        self.state().statements.clone()
+    }
+
+    /// All recorded history rows, including failed ones, in insertion order.
+    pub fn history(&self) -> Vec<AppliedMigration> {
+        self.state().history.clone()
+    }
+
+    fn state(&self) -> MutexGuard<'_, InMemoryState> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[async_trait]
+impl MigrationExecutor for InMemoryExecutor {
+    fn keyspace(&self) -> &str {
+        &self.keyspace
+    }
+
+    fn session(&self) -> Option<&Session> {
+        None
+    }
+
+    async fn execute(&self, statement: &str) -> Result<(), QueryError> {
+        let mut state = self.state();
+
+        let failure = state
+            .failures
+            .iter()
+            .find(|(fragment, _)| statement.contains(fragment.as_str()))
+            .map(|(_, error)| error.clone());
+
+        match failure {
+            Some(error) => Err(error),
+            None => {
+                state.statements.push(statement.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    async fn find_replication(&self) -> Result<Option<HashMap<String, String>>, QueryError> {
+        Ok(self.state().replication.clone())
+    }
+
+    async fn create_history_table(&self) -> Result<(), QueryError> {
+        Ok(())
+    }
+
+    async fn find_applied_migrations(&self) -> Result<Vec<AppliedMigration>, QueryError> {
+        let mut applied_migrations = self
+            .state()
+            .history
+            .iter()
+            .filter(|applied_migration| applied_migration.success)
+            .cloned()
+            .collect::<Vec<_>>();
+        applied_migrations.reverse();
+
+        Ok(applied_migrations)
+    }
+
+    async fn insert_history(&self, applied_migration: &AppliedMigration) -> Result<(), QueryError> {
+        let mut state = self.state();
+        if let Some(error) = &state.history_failure {
+            return Err(error.clone());
+        }
+
+        match state
+            .history
+            .iter_mut()
+            .find(|existing| existing.id == applied_migration.id)
+        {
+            Some(existing) => *existing = applied_migration.clone(),
+            None => state.history.push(applied_migration.clone()),
+        }
+
+        Ok(())
+    }
+
+    async fn delete_failed_history(&self) -> Result<(), QueryError> {
+        self.state()
+            .history
+            .retain(|applied_migration| applied_migration.success);
+        Ok(())
+    }
+
+    async fn await_schema_agreement(&self) -> Result<(), QueryError> {
+        Ok(())
+    }
+}
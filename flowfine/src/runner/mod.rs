@@ -0,0 +1,1750 @@
+use crate::config::{FilenameConvention, KeyspaceDefinition, MigrationConfig, VersionFormatting};
+use crate::migration::rust::{RustMigration, RustMigrationError};
+use crate::migration::version::compare_versions;
+use crate::migration::{Migration, MigrationKind};
+use crate::runner::callback::{CallbackError, MigrationCallback};
+use crate::runner::event::{EventSink, MigrationEvent, StdoutSink};
+use crate::runner::executor::{MigrationExecutor, ScyllaExecutor};
+use crate::runner::plan::{
+    history_checksum, AppliedState, MigrationPlan, PlanAction, PlannedMigration,
+};
+use crate::runner::MigrationExecutionError::*;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use lazy_static::lazy_static;
+use nanoid::nanoid;
+use scylla::frame::protocol_features::ProtocolFeatures;
+use scylla::transport::errors::{DbError, QueryError};
+use scylla::{FromRow, Session};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::env;
+use std::fmt::{self, Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration as StdDuration, Instant};
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+
+pub mod callback;
+pub mod event;
+pub mod executor;
+pub mod in_memory;
+pub mod plan;
+
+lazy_static! {
+    static ref NANOID_LENGTH: usize = 15;
+    static ref FLOWFINE_VERSION: &'static str = env!("CARGO_PKG_VERSION");
+    static ref BASELINE_FILENAME: &'static str = "<< Flowfine Baseline >>";
+    static ref MAX_STATEMENT_LENGTH: usize = 200;
+}
+
+#[derive(Error, Debug)]
+pub enum MigrationExecutionError {
+    #[error("Migration table was not created: {0}")]
+    CreateHistoryTableError(QueryError),
+
+    #[error(transparent)]
+    RunMigrationError(Box<StatementError>),
+
+    #[error("Migration history could not be read: {0}")]
+    FindHistoryError(QueryError),
+
+    #[error("Migration history could not be applied: {0}")]
+    ApplyHistoryError(QueryError),
+
+    #[error("Checksum of migration {0} does not match the applied one")]
+    ChecksumMismatchError(String),
+
+    #[error("Applied migration version {0} was not found locally")]
+    MissingAppliedMigrationError(String),
+
+    #[error("Callback {0} failed: {1}")]
+    CallbackError(String, CallbackError),
+
+    #[error("Keyspace could not be created: {0}")]
+    CreateKeyspaceError(QueryError),
+
+    #[error("Schema agreement was not reached: {0}")]
+    SchemaAgreementError(QueryError),
+
+    #[error("Migration history changed since the plan was made")]
+    StalePlanError,
+
+    #[error("Plan was made for keyspace {0}")]
+    PlanKeyspaceMismatchError(String),
+
+    #[error("Rust migration {0} failed: {1}")]
+    RustMigrationError(String, RustMigrationError),
+
+    #[error("Rust migration {0} requires an executor with a session")]
+    MissingSessionError(String),
+
+    #[error("Rust migration {0} is not registered with the runner")]
+    MissingRustMigrationError(String),
+
+    #[error("Callback {0} requires an executor with a session")]
+    MissingCallbackSessionError(String),
+
+    #[error("Undo migration for version {0} was not found locally")]
+    MissingUndoMigrationError(String),
+
+    /// Carries the migrations applied before the run was cancelled.
+    #[error("Migration run was cancelled after applying {} migrations", .0.len())]
+    Cancelled(Vec<AppliedMigration>),
+
+    #[error("Baseline requires an empty migration history")]
+    BaselineNotEmptyError,
+
+    #[error("Migration history could not be repaired: {0}")]
+    RepairError(QueryError),
+
+    #[error("Keyspace could not be cleaned: {0}")]
+    CleanError(QueryError),
+
+    #[error("Time budget exceeded, migrations not attempted: {}", .not_attempted.join(", "))]
+    TimeBudgetExceededError {
+        applied: Vec<AppliedMigration>,
+        not_attempted: Vec<String>,
+    },
+}
+
+/// Context of a statement that failed while applying a migration.
+#[derive(Clone, Debug)]
+pub struct StatementError {
+    pub filename: String,
+    /// Empty for repeatable migrations.
+    pub version: String,
+    /// Position of the statement in the migration, starting at 0.
+    pub index: usize,
+    /// Statement text, truncated to 200 characters.
+    pub statement: String,
+    /// Line of the migration file on which the statement starts.
+    pub line: Option<usize>,
+    /// Error code sent by the server, e.g. `0x2200` for invalid queries.
+    pub code: Option<i32>,
+    /// Whether the failure is transient, e.g. a timeout or an unavailable replica.
+    pub retryable: bool,
+    pub source: QueryError,
+}
+
+impl StatementError {
+    pub fn new(migration: &Migration, index: usize, source: QueryError) -> Self {
+        let statement = migration
+            .queries
+            .get(index)
+            .map(|query| truncate_statement(query))
+            .unwrap_or_default();
+        let code = match &source {
+            QueryError::DbError(db_error, _) => Some(db_error.code(&ProtocolFeatures::default())),
+            _ => None,
+        };
+
+        Self {
+            filename: migration.filename.clone(),
+            version: migration.version.clone(),
+            index,
+            statement,
+            line: migration.query_line(index),
+            code,
+            retryable: is_retryable(&source),
+            source,
+        }
+    }
+}
+
+impl Display for StatementError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Migration {} failed at statement {}",
+            self.filename,
+            self.index + 1
+        )?;
+        if let Some(line) = self.line {
+            write!(f, " on line {}", line)?;
+        }
+        write!(f, ": {}", self.source)?;
+        if let Some(code) = self.code {
+            write!(f, " (code {:#06x})", code)?;
+        }
+        if self.retryable {
+            write!(f, ", retryable")?;
+        }
+
+        write!(f, "\n  {}", self.statement)
+    }
+}
+
+impl std::error::Error for StatementError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+#[derive(Clone, Debug, FromRow)]
+pub struct AppliedMigration {
+    pub id: String,
+    pub version: String,
+    pub name: String,
+    pub filename: String,
+    pub checksum: String,
+    pub applied_at: Duration,
+    pub success: bool,
+    /// Set when the run was cancelled between two statements of this migration, see `is_interrupted`.
+    pub interrupted: Option<bool>,
+    /// Time spent executing the migration's statements, in milliseconds.
+    ///
+    /// This and the following fields are `None` for rows written before they were recorded.
+    pub execution_time: Option<i64>,
+    pub executed_by: Option<String>,
+    pub statement_count: Option<i32>,
+    pub flowfine_version: Option<String>,
+}
+
+impl AppliedMigration {
+    /// Rows written before interruptions were recorded have no value and count as not interrupted.
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.unwrap_or(false)
+    }
+}
+
+#[async_trait]
+pub trait MigrationRunner {
+    /// Computes the actions needed to bring the keyspace in line with `migrations`.
+    async fn plan(
+        &self,
+        migrations: &[Migration],
+    ) -> Result<MigrationPlan, MigrationExecutionError>;
+
+    /// Executes a plan as is, refusing it if the history changed since it was made.
+    ///
+    /// Once `cancellation` is triggered the statement in flight is completed, the migration is
+    /// recorded as interrupted and `MigrationExecutionError::Cancelled` is returned.
+    async fn execute_with_cancellation(
+        &self,
+        plan: MigrationPlan,
+        cancellation: CancellationToken,
+    ) -> Result<Vec<AppliedMigration>, MigrationExecutionError>;
+
+    async fn execute(
+        &self,
+        plan: MigrationPlan,
+    ) -> Result<Vec<AppliedMigration>, MigrationExecutionError> {
+        self.execute_with_cancellation(plan, CancellationToken::new())
+            .await
+    }
+
+    async fn run_with_cancellation(
+        &self,
+        migrations: Vec<Migration>,
+        cancellation: CancellationToken,
+    ) -> Result<Vec<AppliedMigration>, MigrationExecutionError> {
+        let plan = self.plan(&migrations).await?;
+        self.execute_with_cancellation(plan, cancellation).await
+    }
+
+    async fn run(
+        &self,
+        migrations: Vec<Migration>,
+    ) -> Result<Vec<AppliedMigration>, MigrationExecutionError> {
+        self.run_with_cancellation(migrations, CancellationToken::new())
+            .await
+    }
+
+    /// Marks an existing keyspace as migrated up to `version`, so older migrations are skipped.
+    async fn baseline(
+        &self,
+        version: &str,
+        description: &str,
+    ) -> Result<AppliedMigration, MigrationExecutionError>;
+
+    /// Removes failed history entries and realigns applied checksums with the local files,
+    /// returning the filenames whose checksum was realigned.
+    async fn repair(
+        &self,
+        migrations: &[Migration],
+    ) -> Result<Vec<String>, MigrationExecutionError>;
+
+    /// Drops the keyspace together with its history.
+    async fn clean(&self) -> Result<(), MigrationExecutionError>;
+}
+
+#[derive(Clone, Copy, Debug)]
+enum StopReason {
+    Cancelled,
+    OutOfTime,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum MigrationOutcome {
+    Succeeded,
+    Failed,
+    Interrupted,
+}
+
+pub struct ScyllaMigrationRunner {
+    executor: Arc<dyn MigrationExecutor>,
+    keyspace: String,
+    callbacks: Vec<Arc<dyn MigrationCallback>>,
+    event_sink: Arc<dyn EventSink>,
+    executed_by: String,
+    keyspace_definition: Option<KeyspaceDefinition>,
+    target: Option<String>,
+    run_deadline: Option<StdDuration>,
+    migration_budget: Option<StdDuration>,
+    filename_convention: FilenameConvention,
+    version_formatting: VersionFormatting,
+    rust_migrations: Vec<Arc<dyn RustMigration>>,
+}
+
+impl ScyllaMigrationRunner {
+    pub fn new(session: Arc<Session>, keyspace: &str) -> Self {
+        Self::from_executor(Arc::new(ScyllaExecutor::new(session, keyspace)))
+    }
+
+    /// Runs migrations through a custom executor, e.g. `InMemoryExecutor` in tests.
+    pub fn from_executor(executor: Arc<dyn MigrationExecutor>) -> Self {
+        Self {
+            keyspace: executor.keyspace().to_string(),
+            executor,
+            callbacks: Vec::new(),
+            event_sink: Arc::new(StdoutSink),
+            executed_by: default_executed_by(),
+            keyspace_definition: None,
+            target: None,
+            run_deadline: None,
+            migration_budget: None,
+            filename_convention: FilenameConvention::default(),
+            version_formatting: VersionFormatting::Numeric,
+            rust_migrations: Vec::new(),
+        }
+    }
+
+    pub fn from_config(session: Arc<Session>, config: &MigrationConfig) -> Self {
+        let mut runner = Self::new(session, &config.keyspace);
+        runner.run_deadline = config.run_deadline;
+        runner.migration_budget = config.migration_budget;
+        runner.target = config.target.clone();
+        runner.filename_convention = config.filename_convention.clone();
+        runner.version_formatting = config.version_formatting.clone();
+        if let Some(executed_by) = &config.executed_by {
+            runner.executed_by = executed_by.clone();
+        }
+
+        match &config.keyspace_definition {
+            Some(keyspace_definition) => {
+                runner.with_keyspace_definition(keyspace_definition.clone())
+            }
+            None => runner,
+        }
+    }
+
+    /// Creates the keyspace before running migrations if it does not exist yet.
+    pub fn with_keyspace_definition(mut self, keyspace_definition: KeyspaceDefinition) -> Self {
+        self.keyspace_definition = Some(keyspace_definition);
+        self
+    }
+
+    /// Classifies applied migrations by the filenames recorded in history.
+    pub fn with_filename_convention(mut self, filename_convention: FilenameConvention) -> Self {
+        self.filename_convention = filename_convention;
+        self
+    }
+
+    /// Successful history entries, empty when the keyspace or the history table is missing.
+    pub async fn history(&self) -> Result<Vec<AppliedMigration>, MigrationExecutionError> {
+        self.find_applied_migrations().await
+    }
+
+    /// Orders applied versions against local ones and the target, e.g. `1.9.0` before `1.10.0`.
+    pub fn with_version_formatting(mut self, version_formatting: VersionFormatting) -> Self {
+        self.version_formatting = version_formatting;
+        self
+    }
+
+    /// Overrides the identity recorded in history, which defaults to `user@hostname`.
+    pub fn with_executed_by(mut self, executed_by: &str) -> Self {
+        self.executed_by = executed_by.to_string();
+        self
+    }
+
+    /// Replaces the default stdout sink, e.g. with `NoopSink` to silence the runner.
+    pub fn with_event_sink(mut self, event_sink: Arc<dyn EventSink>) -> Self {
+        self.event_sink = event_sink;
+        self
+    }
+
+    /// Plans undo migrations for applied versions above `target` and skips newer ones.
+    pub fn with_target(mut self, target: &str) -> Self {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    /// Stops the run between statements once it has been running for `run_deadline`.
+    pub fn with_run_deadline(mut self, run_deadline: StdDuration) -> Self {
+        self.run_deadline = Some(run_deadline);
+        self
+    }
+
+    /// Stops the run between statements once a single migration took `migration_budget`.
+    pub fn with_migration_budget(mut self, migration_budget: StdDuration) -> Self {
+        self.migration_budget = Some(migration_budget);
+        self
+    }
+
+    pub fn with_callback(mut self, callback: Arc<dyn MigrationCallback>) -> Self {
+        self.callbacks.push(callback);
+        self
+    }
+
+    /// Runs `rust_migration` when the migrations passed to the runner include it, see
+    /// `parse_with_rust_migrations`.
+    pub fn with_rust_migration(mut self, rust_migration: Arc<dyn RustMigration>) -> Self {
+        self.rust_migrations.push(rust_migration);
+        self
+    }
+
+    fn find_rust_migration(&self, migration: &Migration) -> Option<&dyn RustMigration> {
+        self.rust_migrations
+            .iter()
+            .find(|rust_migration| {
+                migration.kind == MigrationKind::Versioned
+                    && migration.filename
+                        == self
+                            .filename_convention
+                            .rust_filename(rust_migration.version(), rust_migration.description())
+            })
+            .map(|rust_migration| rust_migration.as_ref())
+    }
+
+    async fn apply_rust_migration(
+        &self,
+        migration: &Migration,
+        rust_migration: &dyn RustMigration,
+    ) -> Result<(), MigrationExecutionError> {
+        let session = self
+            .executor
+            .session()
+            .ok_or(MissingSessionError(migration.filename.clone()))?;
+
+        rust_migration
+            .migrate(session)
+            .await
+            .map_err(|err| RustMigrationError(migration.filename.clone(), err))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        skip_all,
+        fields(version = %migration.version, filename = %migration.filename, keyspace = %self.keyspace)
+    ))]
+    async fn apply_migration(
+        &self,
+        migration: &Migration,
+        cancellation: &CancellationToken,
+        run_started_at: Instant,
+    ) -> Result<Option<StopReason>, MigrationExecutionError> {
+        let migration_started_at = Instant::now();
+
+        if self
+            .filename_convention
+            .is_rust_filename(&migration.filename)
+        {
+            let rust_migration = self
+                .find_rust_migration(migration)
+                .ok_or(MissingRustMigrationError(migration.filename.clone()))?;
+            self.apply_rust_migration(migration, rust_migration).await?;
+        }
+
+        for (index, query) in migration.queries.iter().enumerate() {
+            if index > 0 {
+                let stop_reason =
+                    self.stop_reason(cancellation, run_started_at, Some(migration_started_at));
+                if stop_reason.is_some() {
+                    return Ok(stop_reason);
+                }
+            }
+
+            let started_at = Instant::now();
+            let query_future = self.executor.execute(query);
+
+            #[cfg(feature = "tracing")]
+            let query_future = tracing::Instrument::instrument(
+                query_future,
+                tracing::info_span!("statement", index, keyspace = %self.keyspace),
+            );
+
+            query_future.await.map_err(|err| {
+                #[cfg(feature = "tracing")]
+                tracing::error!(error = %err, index, "statement failed");
+
+                RunMigrationError(Box::new(StatementError::new(migration, index, err.clone())))
+            })?;
+
+            self.event_sink.emit(&MigrationEvent::StatementExecuted {
+                version: migration.version.clone(),
+                index,
+                duration: started_at.elapsed(),
+            });
+        }
+
+        self.executor
+            .await_schema_agreement()
+            .await
+            .map(|_| None)
+            .map_err(SchemaAgreementError)
+    }
+
+    fn stop_reason(
+        &self,
+        cancellation: &CancellationToken,
+        run_started_at: Instant,
+        migration_started_at: Option<Instant>,
+    ) -> Option<StopReason> {
+        if cancellation.is_cancelled() {
+            return Some(StopReason::Cancelled);
+        }
+
+        let run_exceeded = self
+            .run_deadline
+            .is_some_and(|run_deadline| run_started_at.elapsed() >= run_deadline);
+        let migration_exceeded = migration_started_at
+            .zip(self.migration_budget)
+            .is_some_and(|(started_at, budget)| started_at.elapsed() >= budget);
+
+        (run_exceeded || migration_exceeded).then_some(StopReason::OutOfTime)
+    }
+
+    fn stopped(
+        &self,
+        stop_reason: StopReason,
+        applied_migrations: Vec<AppliedMigration>,
+        not_attempted: &[&PlannedMigration],
+    ) -> MigrationExecutionError {
+        match stop_reason {
+            StopReason::Cancelled => Cancelled(applied_migrations),
+            StopReason::OutOfTime => TimeBudgetExceededError {
+                applied: applied_migrations,
+                not_attempted: not_attempted
+                    .iter()
+                    .map(|planned| planned.filename.clone())
+                    .collect(),
+            },
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        skip_all,
+        fields(version = %migration.version, filename = %migration.filename, keyspace = %self.keyspace, outcome = ?outcome)
+    ))]
+    async fn apply_history(
+        &self,
+        outcome: MigrationOutcome,
+        migration: &Migration,
+        checksum: &str,
+        execution_time: StdDuration,
+    ) -> Result<AppliedMigration, MigrationExecutionError> {
+        let nanoid_len = *NANOID_LENGTH;
+        let applied_migration = AppliedMigration {
+            id: nanoid!(nanoid_len).to_string(),
+            version: migration.version.clone(),
+            name: migration.name.clone(),
+            filename: migration.filename.clone(),
+            checksum: checksum.to_string(),
+            applied_at: Duration::nanoseconds(Utc::now().timestamp_nanos_opt().unwrap_or_default()), //todo: move the code to date utils
+            success: matches!(outcome, MigrationOutcome::Succeeded),
+            interrupted: Some(matches!(outcome, MigrationOutcome::Interrupted)),
+            execution_time: Some(execution_time.as_millis() as i64),
+            executed_by: Some(self.executed_by.clone()),
+            statement_count: Some(migration.queries.len() as i32),
+            flowfine_version: Some(FLOWFINE_VERSION.to_string()),
+        };
+
+        self.executor
+            .insert_history(&applied_migration)
+            .await
+            .map(|_| applied_migration)
+            .map_err(|err| {
+                #[cfg(feature = "tracing")]
+                tracing::error!(error = %err, "history insert failed");
+
+                ApplyHistoryError(err.clone())
+            })
+    }
+
+    async fn find_applied_migrations(
+        &self,
+    ) -> Result<Vec<AppliedMigration>, MigrationExecutionError> {
+        self.executor
+            .find_applied_migrations()
+            .await
+            .map_err(FindHistoryError)
+    }
+
+    fn verify_checksums(
+        &self,
+        migrations: &[Migration],
+        applied_migrations: &[AppliedMigration],
+    ) -> Result<(), MigrationExecutionError> {
+        let versioned_migrations = applied_migrations.iter().filter(|applied_migration| {
+            self.filename_convention.kind(&applied_migration.filename)
+                == Some(MigrationKind::Versioned)
+        });
+
+        for applied_migration in versioned_migrations {
+            let migration = migrations
+                .iter()
+                .find(|migration| {
+                    migration.kind == MigrationKind::Versioned
+                        && migration.version == applied_migration.version
+                })
+                .ok_or(MissingAppliedMigrationError(
+                    applied_migration.version.clone(),
+                ))?;
+
+            if self.create_checksum(migration) != applied_migration.checksum {
+                return Err(ChecksumMismatchError(migration.filename.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn create_checksum(&self, migration: &Migration) -> String {
+        let checksum = Sha256::new()
+            .chain_update(migration.version.as_bytes())
+            .chain_update(migration.name.as_bytes())
+            .chain_update(migration.content.as_bytes())
+            .finalize();
+
+        format!("{:x}", checksum)
+    }
+
+    fn compare_versions(&self, a: &str, b: &str) -> Ordering {
+        compare_versions(&self.version_formatting, a, b)
+    }
+
+    fn build_plan(
+        &self,
+        migrations: &[Migration],
+        applied_migrations: &[AppliedMigration],
+    ) -> Result<MigrationPlan, MigrationExecutionError> {
+        let state = AppliedState::from_history(applied_migrations, &self.filename_convention);
+        let mut actions = Vec::new();
+
+        let undone_versions = state
+            .versions
+            .iter()
+            .rev()
+            .filter(|version| {
+                self.target
+                    .as_ref()
+                    .is_some_and(|target| self.compare_versions(version, target).is_gt())
+            })
+            .collect::<Vec<_>>();
+
+        for version in &undone_versions {
+            let undo_migration = migrations
+                .iter()
+                .find(|migration| {
+                    migration.kind == MigrationKind::Undo && migration.version == **version
+                })
+                .ok_or(MissingUndoMigrationError(version.to_string()))?;
+            actions.push(self.plan_migration(PlanAction::Undo, undo_migration));
+        }
+
+        let latest_version = state
+            .versions
+            .iter()
+            .rev()
+            .find(|version| !undone_versions.contains(version));
+
+        for migration in migrations {
+            let action = match migration.kind {
+                MigrationKind::Versioned => {
+                    let is_newer = latest_version.is_none_or(|latest| {
+                        self.compare_versions(latest, &migration.version).is_lt()
+                    });
+                    let is_targeted = self.target.as_ref().is_none_or(|target| {
+                        self.compare_versions(&migration.version, target).is_le()
+                    });
+
+                    match is_newer && is_targeted {
+                        true => PlanAction::Apply,
+                        false => PlanAction::Skip,
+                    }
+                }
+                MigrationKind::Repeatable => {
+                    match state.repeatable_checksums.get(&migration.filename) {
+                        None => PlanAction::Apply,
+                        Some(checksum) if *checksum != self.create_checksum(migration) => {
+                            PlanAction::Reapply
+                        }
+                        Some(_) => PlanAction::Skip,
+                    }
+                }
+                MigrationKind::Undo => continue,
+            };
+            if action != PlanAction::Skip
+                && self
+                    .filename_convention
+                    .is_rust_filename(&migration.filename)
+                && self.find_rust_migration(migration).is_none()
+            {
+                return Err(MissingRustMigrationError(migration.filename.clone()));
+            }
+            actions.push(self.plan_migration(action, migration));
+        }
+
+        Ok(MigrationPlan {
+            keyspace: self.keyspace.clone(),
+            history_checksum: history_checksum(applied_migrations),
+            actions,
+        })
+    }
+
+    fn plan_migration(&self, action: PlanAction, migration: &Migration) -> PlannedMigration {
+        PlannedMigration::new(action, migration, self.create_checksum(migration))
+    }
+
+    async fn create_history_table(&self) -> Result<(), MigrationExecutionError> {
+        self.executor
+            .create_history_table()
+            .await
+            .map_err(CreateHistoryTableError)
+    }
+
+    async fn ensure_keyspace(&self) -> Result<(), MigrationExecutionError> {
+        let keyspace_definition = match &self.keyspace_definition {
+            Some(keyspace_definition) => keyspace_definition,
+            None => return Ok(()),
+        };
+
+        let live_replication = self
+            .executor
+            .find_replication()
+            .await
+            .map_err(CreateKeyspaceError)?;
+
+        match live_replication {
+            Some(live_replication) => {
+                if !keyspace_definition.replication.matches(&live_replication) {
+                    self.event_sink.emit(&MigrationEvent::ReplicationMismatch {
+                        keyspace: self.keyspace.clone(),
+                        expected: keyspace_definition.replication.to_cql(),
+                        actual: format!("{:?}", live_replication),
+                    });
+                }
+            }
+            None => {
+                self.executor
+                    .execute(&keyspace_definition.to_cql(&self.keyspace))
+                    .await
+                    .map_err(CreateKeyspaceError)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn migrate(
+        &self,
+        plan: MigrationPlan,
+        cancellation: &CancellationToken,
+        run_started_at: Instant,
+    ) -> Result<Vec<AppliedMigration>, MigrationExecutionError> {
+        let mut applied_migrations = Vec::new();
+
+        let history = self.find_applied_migrations().await?;
+        if history_checksum(&history) != plan.history_checksum {
+            return Err(StalePlanError);
+        }
+
+        let pending = plan.pending().collect::<Vec<_>>();
+        let pending_migrations = pending
+            .iter()
+            .map(|planned| planned.to_migration())
+            .collect::<Vec<_>>();
+
+        self.invoke_callbacks("before_migrate", |callback, session| {
+            callback.before_migrate(session, &pending_migrations)
+        })
+        .await?;
+
+        for (position, (planned, migration)) in pending.iter().zip(&pending_migrations).enumerate()
+        {
+            if let Some(stop_reason) = self.stop_reason(cancellation, run_started_at, None) {
+                return Err(self.stopped(stop_reason, applied_migrations, &pending[position..]));
+            }
+
+            self.invoke_callbacks("before_each", |callback, session| {
+                callback.before_each(session, migration)
+            })
+            .await?;
+
+            self.event_sink.emit(&MigrationEvent::MigrationStarted {
+                version: migration.version.clone(),
+                filename: migration.filename.clone(),
+            });
+            let started_at = Instant::now();
+
+            match self
+                .apply_migration(migration, cancellation, run_started_at)
+                .await
+            {
+                Ok(None) => {
+                    let applied_migration = self
+                        .apply_history(
+                            MigrationOutcome::Succeeded,
+                            migration,
+                            &planned.checksum,
+                            started_at.elapsed(),
+                        )
+                        .await?;
+                    self.event_sink.emit(&MigrationEvent::MigrationFinished {
+                        version: migration.version.clone(),
+                        filename: migration.filename.clone(),
+                        duration: started_at.elapsed(),
+                    });
+                    self.invoke_callbacks("after_each", |callback, session| {
+                        callback.after_each(session, migration, &applied_migration)
+                    })
+                    .await?;
+                    applied_migrations.push(applied_migration);
+
+                    let not_attempted = &pending[position + 1..];
+                    let stop_reason =
+                        self.stop_reason(cancellation, run_started_at, Some(started_at));
+                    if let (Some(stop_reason), false) = (stop_reason, not_attempted.is_empty()) {
+                        return Err(self.stopped(stop_reason, applied_migrations, not_attempted));
+                    }
+                }
+                Ok(Some(stop_reason)) => {
+                    self.apply_history(
+                        MigrationOutcome::Interrupted,
+                        migration,
+                        &planned.checksum,
+                        started_at.elapsed(),
+                    )
+                    .await?;
+                    self.event_sink.emit(&MigrationEvent::MigrationInterrupted {
+                        version: migration.version.clone(),
+                        filename: migration.filename.clone(),
+                    });
+                    let not_attempted = &pending[position + 1..];
+                    return Err(self.stopped(stop_reason, applied_migrations, not_attempted));
+                }
+                Err(err) => {
+                    // The migration error is what the caller needs to see, so failures while
+                    // recording it are only reported.
+                    let recorded = self
+                        .apply_history(
+                            MigrationOutcome::Failed,
+                            migration,
+                            &planned.checksum,
+                            started_at.elapsed(),
+                        )
+                        .await
+                        .map(|_| ());
+                    self.event_sink.emit(&MigrationEvent::MigrationFailed {
+                        version: migration.version.clone(),
+                        filename: migration.filename.clone(),
+                        error: err.to_string(),
+                    });
+                    let notified = self
+                        .invoke_callbacks("after_each_error", |callback, session| {
+                            callback.after_each_error(session, migration, &err)
+                        })
+                        .await;
+                    for handling_error in [recorded, notified].into_iter().filter_map(Result::err) {
+                        self.event_sink
+                            .emit(&MigrationEvent::FailureHandlingFailed {
+                                version: migration.version.clone(),
+                                filename: migration.filename.clone(),
+                                error: handling_error.to_string(),
+                            });
+                    }
+
+                    return Err(err);
+                }
+            };
+        }
+
+        self.invoke_callbacks("after_migrate", |callback, session| {
+            callback.after_migrate(session, &applied_migrations)
+        })
+        .await?;
+
+        Ok(applied_migrations)
+    }
+
+    async fn invoke_callbacks<'a, F>(
+        &'a self,
+        name: &str,
+        hook: F,
+    ) -> Result<(), MigrationExecutionError>
+    where
+        F: Fn(
+            &'a dyn MigrationCallback,
+            &'a Session,
+        ) -> Pin<Box<dyn Future<Output = Result<(), CallbackError>> + Send + 'a>>,
+    {
+        if self.callbacks.is_empty() {
+            return Ok(());
+        }
+
+        let session = self
+            .executor
+            .session()
+            .ok_or(MissingCallbackSessionError(name.to_string()))?;
+        for callback in &self.callbacks {
+            hook(callback.as_ref(), session)
+                .await
+                .map_err(|err| CallbackError(name.to_string(), err))?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks the checksums of local migrations against the applied ones.
+    pub async fn validate(&self, migrations: &[Migration]) -> Result<(), MigrationExecutionError> {
+        let applied_migrations = self.find_applied_migrations().await?;
+
+        let result = self.verify_checksums(migrations, &applied_migrations);
+        if let Err(err) = &result {
+            self.invoke_callbacks("after_validate_error", |callback, session| {
+                callback.after_validate_error(session, err)
+            })
+            .await?;
+        }
+
+        result
+    }
+}
+
+#[async_trait]
+impl MigrationRunner for ScyllaMigrationRunner {
+    /// Only reads history, so planning against a missing keyspace or history table plans every
+    /// migration without creating any schema.
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        skip_all,
+        fields(keyspace = %self.keyspace, migrations = migrations.len())
+    ))]
+    async fn plan(
+        &self,
+        migrations: &[Migration],
+    ) -> Result<MigrationPlan, MigrationExecutionError> {
+        let applied_migrations = self.find_applied_migrations().await?;
+
+        self.build_plan(migrations, &applied_migrations)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        skip_all,
+        fields(keyspace = %self.keyspace, actions = plan.actions.len())
+    ))]
+    async fn execute_with_cancellation(
+        &self,
+        plan: MigrationPlan,
+        cancellation: CancellationToken,
+    ) -> Result<Vec<AppliedMigration>, MigrationExecutionError> {
+        if plan.keyspace != self.keyspace {
+            return Err(PlanKeyspaceMismatchError(plan.keyspace));
+        }
+
+        let started_at = Instant::now();
+        self.event_sink.emit(&MigrationEvent::RunStarted {
+            keyspace: self.keyspace.clone(),
+        });
+
+        self.ensure_keyspace().await?;
+        self.create_history_table().await?;
+        let applied_migrations = self.migrate(plan, &cancellation, started_at).await?;
+
+        self.event_sink.emit(&MigrationEvent::RunFinished {
+            applied: applied_migrations.len(),
+            duration: started_at.elapsed(),
+        });
+
+        Ok(applied_migrations)
+    }
+
+    async fn baseline(
+        &self,
+        version: &str,
+        description: &str,
+    ) -> Result<AppliedMigration, MigrationExecutionError> {
+        self.ensure_keyspace().await?;
+        self.create_history_table().await?;
+
+        if !self.find_applied_migrations().await?.is_empty() {
+            return Err(BaselineNotEmptyError);
+        }
+
+        let baseline = Migration {
+            filename: BASELINE_FILENAME.to_string(),
+            kind: MigrationKind::Versioned,
+            version: version.to_string(),
+            version_key: None,
+            name: description.to_string(),
+            content: String::new(),
+            queries: Vec::new(),
+        };
+
+        self.apply_history(
+            MigrationOutcome::Succeeded,
+            &baseline,
+            "",
+            StdDuration::ZERO,
+        )
+        .await
+    }
+
+    async fn repair(
+        &self,
+        migrations: &[Migration],
+    ) -> Result<Vec<String>, MigrationExecutionError> {
+        self.create_history_table().await?;
+        self.executor
+            .delete_failed_history()
+            .await
+            .map_err(RepairError)?;
+
+        let mut realigned = Vec::new();
+        for applied_migration in self.find_applied_migrations().await? {
+            let migration = migrations
+                .iter()
+                .find(|migration| migration.filename == applied_migration.filename);
+            let checksum = match migration {
+                Some(migration) => self.create_checksum(migration),
+                None => continue,
+            };
+
+            if checksum != applied_migration.checksum {
+                let repaired_migration = AppliedMigration {
+                    checksum,
+                    ..applied_migration
+                };
+                self.executor
+                    .insert_history(&repaired_migration)
+                    .await
+                    .map_err(RepairError)?;
+                realigned.push(repaired_migration.filename);
+            }
+        }
+
+        Ok(realigned)
+    }
+
+    async fn clean(&self) -> Result<(), MigrationExecutionError> {
+        self.executor
+            .execute(&format!("DROP KEYSPACE IF EXISTS {};", self.keyspace))
+            .await
+            .map_err(CleanError)
+    }
+}
+
+fn truncate_statement(statement: &str) -> String {
+    let max_length = *MAX_STATEMENT_LENGTH;
+
+    match statement.char_indices().nth(max_length) {
+        Some((end, _)) => format!("{}...", &statement[..end]),
+        None => statement.to_string(),
+    }
+}
+
+fn is_retryable(err: &QueryError) -> bool {
+    match err {
+        QueryError::DbError(db_error, _) => matches!(
+            db_error,
+            DbError::Unavailable { .. }
+                | DbError::Overloaded
+                | DbError::IsBootstrapping
+                | DbError::ReadTimeout { .. }
+                | DbError::WriteTimeout { .. }
+                | DbError::RateLimitReached { .. }
+                | DbError::ServerError
+        ),
+        QueryError::IoError(_)
+        | QueryError::TimeoutError
+        | QueryError::RequestTimeout(_)
+        | QueryError::TooManyOrphanedStreamIds(_)
+        | QueryError::UnableToAllocStreamId => true,
+        _ => false,
+    }
+}
+
+fn default_executed_by() -> String {
+    let user = env::var("USER").or_else(|_| env::var("USERNAME")).ok();
+    format_executed_by(user, hostname())
+}
+
+fn format_executed_by(user: Option<String>, hostname: Option<String>) -> String {
+    format!(
+        "{}@{}",
+        user.unwrap_or_else(|| "unknown".to_string()),
+        hostname.unwrap_or_else(|| "unknown".to_string())
+    )
+}
+
+#[cfg(unix)]
+fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: gethostname writes at most `buf.len()` bytes into `buf`
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if result != 0 {
+        return None;
+    }
+
+    let end = buf.iter().position(|&byte| byte == 0).unwrap_or(buf.len());
+    String::from_utf8(buf[..end].to_vec())
+        .ok()
+        .filter(|hostname| !hostname.is_empty())
+}
+
+#[cfg(not(unix))]
+fn hostname() -> Option<String> {
+    env::var("COMPUTERNAME").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::VersionFormatting;
+    use crate::migration::lexer::delimit_queries;
+    use crate::migration::rust::to_migration;
+    use crate::migration::version::MigrationVersionKey;
+    use crate::runner::event::NoopSink;
+    use crate::runner::in_memory::InMemoryExecutor;
+    use rstest::rstest;
+
+    #[tokio::test]
+    async fn test_apply_pending_migrations() {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let runner = new_runner(executor.clone());
+        let migrations = vec![
+            new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);"),
+            new_migration("2", "INSERT INTO foo (id) VALUES (1);"),
+        ];
+
+        // when
+        let applied_migrations = runner.run(migrations).await.unwrap();
+
+        // then
+        assert_eq!(applied_migrations.len(), 2);
+        assert_eq!(
+            executor.executed_statements(),
+            vec![
+                "CREATE TABLE foo (id int PRIMARY KEY);",
+                "INSERT INTO foo (id) VALUES (1);",
+            ]
+        );
+        assert!(executor.history().iter().all(|applied| applied.success));
+    }
+
+    #[tokio::test]
+    async fn test_skip_applied_migrations() {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let runner = new_runner(executor.clone());
+        let first_migration = new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);");
+        let second_migration = new_migration("2", "INSERT INTO foo (id) VALUES (1);");
+        runner.run(vec![first_migration.clone()]).await.unwrap();
+
+        // when
+        let applied_migrations = runner
+            .run(vec![first_migration, second_migration])
+            .await
+            .unwrap();
+
+        // then
+        assert_eq!(applied_migrations.len(), 1);
+        assert_eq!(applied_migrations[0].version, "2");
+        assert_eq!(executor.executed_statements().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_record_failed_migration() {
+        // given
+        let error = QueryError::InvalidMessage("table foo already exists".to_string());
+        let executor = Arc::new(InMemoryExecutor::new("flowfine").fail_on("CREATE TABLE", error));
+        let runner = new_runner(executor.clone());
+        let migrations = vec![
+            new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);"),
+            new_migration("2", "INSERT INTO foo (id) VALUES (1);"),
+        ];
+
+        // when
+        let result = runner.run(migrations).await;
+
+        // then
+        assert!(matches!(result, Err(RunMigrationError(..))));
+        let history = executor.history();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].version, "1");
+        assert!(!history[0].success);
+        assert!(executor.executed_statements().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_return_migration_error_when_recording_failure_fails() {
+        // given
+        let error = QueryError::InvalidMessage("table foo already exists".to_string());
+        let history_error = QueryError::InvalidMessage("history unavailable".to_string());
+        let executor = Arc::new(
+            InMemoryExecutor::new("flowfine")
+                .fail_on("CREATE TABLE", error)
+                .fail_history_inserts(history_error),
+        );
+        let event_sink = Arc::new(RecordingSink::default());
+        let runner = new_runner(executor.clone()).with_event_sink(event_sink.clone());
+        let migrations = vec![new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);")];
+
+        // when
+        let result = runner.run(migrations).await;
+
+        // then
+        assert!(matches!(result, Err(RunMigrationError(..))));
+        assert!(executor.history().is_empty());
+        assert!(event_sink
+            .events()
+            .contains(&MigrationEvent::FailureHandlingFailed {
+                version: "1".to_string(),
+                filename: "V1__migration.cql".to_string(),
+                error:
+                    "Migration history could not be applied: Invalid message: history unavailable"
+                        .to_string(),
+            }));
+    }
+
+    #[tokio::test]
+    async fn test_report_failed_statement_context() {
+        // given
+        let error = QueryError::DbError(DbError::Invalid, "unknown column name".to_string());
+        let executor = Arc::new(InMemoryExecutor::new("flowfine").fail_on("ALTER TABLE", error));
+        let runner = new_runner(executor);
+        let content = "CREATE TABLE foo (id int PRIMARY KEY);\n\nALTER TABLE foo\n  DROP name;";
+        let migration = Migration {
+            content: content.to_string(),
+            queries: delimit_queries("V1__migration.cql", content).unwrap(),
+            ..new_migration("1", content)
+        };
+
+        // when
+        let result = runner.run(vec![migration]).await;
+
+        // then
+        match result {
+            Err(RunMigrationError(err)) => {
+                assert_eq!(err.version, "1");
+                assert_eq!(err.index, 1);
+                assert_eq!(err.line, Some(3));
+                assert_eq!(err.statement, "ALTER TABLE foo\n  DROP name;");
+                assert_eq!(err.code, Some(0x2200));
+                assert!(!err.retryable);
+            }
+            _ => panic!("expected the failed statement to be reported"),
+        }
+    }
+
+    #[rstest(statement, expected_result,
+        case("DROP TABLE foo;", "DROP TABLE foo;"),
+        case(&"x".repeat(201), &format!("{}...", "x".repeat(200))),
+    )]
+    fn test_truncate_statement(statement: &str, expected_result: &str) {
+        assert_eq!(truncate_statement(statement), expected_result);
+    }
+
+    #[rstest(
+        user,
+        hostname,
+        expected_result,
+        case(Some("alice"), Some("box"), "alice@box"),
+        case(Some("alice"), None, "alice@unknown"),
+        case(None, Some("box"), "unknown@box")
+    )]
+    fn test_format_executed_by(user: Option<&str>, hostname: Option<&str>, expected_result: &str) {
+        assert_eq!(
+            format_executed_by(user.map(String::from), hostname.map(String::from)),
+            expected_result
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_look_up_hostname() {
+        // when
+        let hostname = hostname();
+
+        // then
+        assert!(hostname.is_some_and(|hostname| !hostname.is_empty()));
+    }
+
+    #[rstest(
+        version_formatting,
+        first_version,
+        second_version,
+        case(VersionFormatting::Semver, "1.9.0", "1.10.0"),
+        case(VersionFormatting::Numeric, "9", "10")
+    )]
+    #[tokio::test]
+    async fn test_order_versions_by_formatting(
+        version_formatting: VersionFormatting,
+        first_version: &str,
+        second_version: &str,
+    ) {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let runner = new_runner(executor.clone()).with_version_formatting(version_formatting);
+        let first_migration =
+            new_migration(first_version, "CREATE TABLE foo (id int PRIMARY KEY);");
+        let second_migration = new_migration(second_version, "INSERT INTO foo (id) VALUES (1);");
+        runner.run(vec![first_migration.clone()]).await.unwrap();
+
+        // when
+        let applied_migrations = runner
+            .run(vec![first_migration, second_migration])
+            .await
+            .unwrap();
+
+        // then
+        assert_eq!(applied_migrations.len(), 1);
+        assert_eq!(applied_migrations[0].version, second_version);
+    }
+
+    #[tokio::test]
+    async fn test_plan_pending_and_applied_migrations() {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let runner = new_runner(executor.clone());
+        let first_migration = new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);");
+        let second_migration = new_migration("2", "INSERT INTO foo (id) VALUES (1);");
+        runner.run(vec![first_migration.clone()]).await.unwrap();
+
+        // when
+        let plan = runner
+            .plan(&[first_migration, second_migration])
+            .await
+            .unwrap();
+
+        // then
+        let actions = plan
+            .actions
+            .iter()
+            .map(|planned| (planned.version.as_str(), planned.action))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![("1", PlanAction::Skip), ("2", PlanAction::Apply)]
+        );
+        assert_eq!(executor.executed_statements().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_execute_plan_as_approved() {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let runner = new_runner(executor.clone());
+        let migrations = vec![new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);")];
+        let plan = runner.plan(&migrations).await.unwrap();
+        let serialized = serde_json::to_string(&plan).unwrap();
+
+        // when
+        let approved_plan: MigrationPlan = serde_json::from_str(&serialized).unwrap();
+        let applied_migrations = runner.execute(approved_plan).await.unwrap();
+
+        // then
+        assert_eq!(applied_migrations.len(), 1);
+        assert_eq!(applied_migrations[0].checksum, plan.actions[0].checksum);
+        assert_eq!(executor.executed_statements(), migrations[0].queries);
+    }
+
+    #[tokio::test]
+    async fn test_refuse_stale_plan() {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let runner = new_runner(executor.clone());
+        let migrations = vec![new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);")];
+        let plan = runner.plan(&migrations).await.unwrap();
+        runner.run(migrations).await.unwrap();
+
+        // when
+        let result = runner.execute(plan).await;
+
+        // then
+        assert!(matches!(result, Err(StalePlanError)));
+        assert_eq!(executor.executed_statements().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reapply_changed_repeatable_migration() {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let runner = new_runner(executor.clone());
+        let repeatable = new_script(MigrationKind::Repeatable, "", "CREATE INDEX a ON foo (id);");
+        let changed = new_script(MigrationKind::Repeatable, "", "CREATE INDEX b ON foo (id);");
+        runner.run(vec![repeatable.clone()]).await.unwrap();
+
+        // when
+        let unchanged_plan = runner.plan(&[repeatable]).await.unwrap();
+        let changed_plan = runner.plan(&[changed]).await.unwrap();
+
+        // then
+        assert_eq!(unchanged_plan.actions[0].action, PlanAction::Skip);
+        assert_eq!(changed_plan.actions[0].action, PlanAction::Reapply);
+    }
+
+    #[tokio::test]
+    async fn test_undo_migrations_above_target() {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let migrations = vec![
+            new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);"),
+            new_migration("2", "CREATE TABLE bar (id int PRIMARY KEY);"),
+            new_script(MigrationKind::Undo, "2", "DROP TABLE bar;"),
+        ];
+        new_runner(executor.clone())
+            .run(migrations.clone())
+            .await
+            .unwrap();
+        let runner = new_runner(executor.clone()).with_target("1");
+
+        // when
+        let plan = runner.plan(&migrations).await.unwrap();
+        runner.execute(plan.clone()).await.unwrap();
+
+        // then
+        let actions = plan
+            .actions
+            .iter()
+            .map(|planned| (planned.kind, planned.version.as_str(), planned.action))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![
+                (MigrationKind::Undo, "2", PlanAction::Undo),
+                (MigrationKind::Versioned, "1", PlanAction::Skip),
+                (MigrationKind::Versioned, "2", PlanAction::Skip),
+            ]
+        );
+        assert_eq!(
+            executor.executed_statements().last().unwrap(),
+            "DROP TABLE bar;"
+        );
+
+        let replan = new_runner(executor).plan(&migrations).await.unwrap();
+        assert_eq!(replan.actions[1].action, PlanAction::Apply);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_between_migrations() {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let cancellation = CancellationToken::new();
+        let runner = ScyllaMigrationRunner::from_executor(executor.clone()).with_event_sink(
+            Arc::new(CancelOnEvent::new(cancellation.clone(), |event| {
+                matches!(event, MigrationEvent::MigrationFinished { .. })
+            })),
+        );
+        let migrations = vec![
+            new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);"),
+            new_migration("2", "INSERT INTO foo (id) VALUES (1);"),
+        ];
+
+        // when
+        let result = runner.run_with_cancellation(migrations, cancellation).await;
+
+        // then
+        match result {
+            Err(Cancelled(applied_migrations)) => assert_eq!(applied_migrations.len(), 1),
+            _ => panic!("expected the run to be cancelled"),
+        }
+        assert_eq!(executor.history().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_record_interrupted_migration() {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let cancellation = CancellationToken::new();
+        let runner = ScyllaMigrationRunner::from_executor(executor.clone()).with_event_sink(
+            Arc::new(CancelOnEvent::new(cancellation.clone(), |event| {
+                matches!(event, MigrationEvent::StatementExecuted { .. })
+            })),
+        );
+        let mut migration = new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);");
+        migration
+            .queries
+            .push("INSERT INTO foo (id) VALUES (1);".to_string());
+
+        // when
+        let result = runner
+            .run_with_cancellation(vec![migration], cancellation)
+            .await;
+
+        // then
+        assert!(matches!(result, Err(Cancelled(applied)) if applied.is_empty()));
+        assert_eq!(executor.executed_statements().len(), 1);
+        let history = executor.history();
+        assert_eq!(history.len(), 1);
+        assert!(!history[0].success);
+        assert!(history[0].is_interrupted());
+    }
+
+    #[tokio::test]
+    async fn test_stop_when_migration_budget_is_exceeded() {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let runner = new_runner(executor.clone()).with_migration_budget(StdDuration::ZERO);
+        let mut first_migration = new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);");
+        first_migration
+            .queries
+            .push("INSERT INTO foo (id) VALUES (1);".to_string());
+        let migrations = vec![
+            first_migration,
+            new_migration("2", "INSERT INTO foo (id) VALUES (2);"),
+        ];
+
+        // when
+        let result = runner.run(migrations).await;
+
+        // then
+        match result {
+            Err(TimeBudgetExceededError {
+                applied,
+                not_attempted,
+            }) => {
+                assert!(applied.is_empty());
+                assert_eq!(not_attempted, vec!["V2__migration.cql"]);
+            }
+            _ => panic!("expected the time budget to be exceeded"),
+        }
+        assert_eq!(executor.executed_statements().len(), 1);
+        assert!(executor.history()[0].is_interrupted());
+    }
+
+    #[tokio::test]
+    async fn test_leave_migrations_pending_after_run_deadline() {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let runner = new_runner(executor.clone()).with_run_deadline(StdDuration::ZERO);
+        let migrations = vec![
+            new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);"),
+            new_migration("2", "INSERT INTO foo (id) VALUES (1);"),
+        ];
+
+        // when
+        let result = runner.run(migrations).await;
+
+        // then
+        assert!(matches!(
+            result,
+            Err(TimeBudgetExceededError { not_attempted, .. }) if not_attempted.len() == 2
+        ));
+        assert!(executor.executed_statements().is_empty());
+        assert!(executor.history().is_empty());
+    }
+
+    struct CancelOnEvent {
+        cancellation: CancellationToken,
+        predicate: fn(&MigrationEvent) -> bool,
+    }
+
+    impl CancelOnEvent {
+        fn new(cancellation: CancellationToken, predicate: fn(&MigrationEvent) -> bool) -> Self {
+            Self {
+                cancellation,
+                predicate,
+            }
+        }
+    }
+
+    impl EventSink for CancelOnEvent {
+        fn emit(&self, event: &MigrationEvent) {
+            if (self.predicate)(event) {
+                self.cancellation.cancel();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_baseline_skips_older_migrations() {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let runner = new_runner(executor.clone());
+        let migrations = vec![
+            new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);"),
+            new_migration("2", "INSERT INTO foo (id) VALUES (1);"),
+        ];
+
+        // when
+        runner.baseline("1", "existing schema").await.unwrap();
+        let applied_migrations = runner.run(migrations.clone()).await.unwrap();
+
+        // then
+        assert_eq!(applied_migrations.len(), 1);
+        assert_eq!(applied_migrations[0].version, "2");
+        assert!(runner.validate(&migrations).await.is_ok());
+        assert!(matches!(
+            runner.baseline("1", "existing schema").await,
+            Err(BaselineNotEmptyError)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_repair_history() {
+        // given
+        let error = QueryError::InvalidMessage("table bar already exists".to_string());
+        let executor =
+            Arc::new(InMemoryExecutor::new("flowfine").fail_on("CREATE TABLE bar", error));
+        let runner = new_runner(executor.clone());
+        let migration = new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);");
+        runner
+            .run(vec![
+                migration.clone(),
+                new_migration("2", "CREATE TABLE bar (id int PRIMARY KEY);"),
+            ])
+            .await
+            .unwrap_err();
+        let edited_migrations = vec![Migration {
+            content: "CREATE TABLE foo (id int PRIMARY KEY); ".to_string(),
+            ..migration
+        }];
+
+        // when
+        let realigned = runner.repair(&edited_migrations).await.unwrap();
+
+        // then
+        assert_eq!(realigned, vec!["V1__migration.cql"]);
+        assert!(executor.history().iter().all(|applied| applied.success));
+        assert!(runner.validate(&edited_migrations).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_require_session_for_rust_migration() {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let rust_migration = Arc::new(BackfillFoo);
+        let runner = new_runner(executor.clone()).with_rust_migration(rust_migration.clone());
+        let migrations = vec![
+            new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);"),
+            to_migration(
+                rust_migration.as_ref(),
+                &VersionFormatting::Numeric,
+                &FilenameConvention::default(),
+            )
+            .unwrap(),
+        ];
+
+        // when
+        let result = runner.run(migrations).await;
+
+        // then
+        assert!(
+            matches!(result, Err(MissingSessionError(filename)) if filename == "V2__backfill_foo.rs")
+        );
+        let history = executor.history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].filename, "V2__backfill_foo.rs");
+        assert!(!history[1].success);
+    }
+
+    #[tokio::test]
+    async fn test_reject_unregistered_rust_migration() {
+        // given
+        let executor = Arc::new(InMemoryExecutor::new("flowfine"));
+        let registered_runner =
+            new_runner(executor.clone()).with_rust_migration(Arc::new(BackfillFoo));
+        let runner = new_runner(executor.clone());
+        let migrations = vec![
+            new_migration("1", "CREATE TABLE foo (id int PRIMARY KEY);"),
+            to_migration(
+                &BackfillFoo,
+                &VersionFormatting::Numeric,
+                &FilenameConvention::default(),
+            )
+            .unwrap(),
+        ];
+        let plan = registered_runner.plan(&migrations).await.unwrap();
+
+        // when
+        let plan_result = runner.plan(&migrations).await;
+        let execute_result = runner.execute(plan).await;
+
+        // then
+        assert!(matches!(
+            plan_result,
+            Err(MissingRustMigrationError(filename)) if filename == "V2__backfill_foo.rs"
+        ));
+        assert!(matches!(
+            execute_result,
+            Err(MissingRustMigrationError(filename)) if filename == "V2__backfill_foo.rs"
+        ));
+        let history = executor.history();
+        assert_eq!(history.len(), 2);
+        assert!(history[0].success);
+        assert!(!history[1].success);
+    }
+
+    struct BackfillFoo;
+
+    #[async_trait]
+    impl RustMigration for BackfillFoo {
+        fn version(&self) -> &str {
+            "2"
+        }
+
+        fn description(&self) -> &str {
+            "backfill foo"
+        }
+
+        async fn migrate(&self, _session: &Session) -> Result<(), RustMigrationError> {
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingSink {
+        events: std::sync::Mutex<Vec<MigrationEvent>>,
+    }
+
+    impl RecordingSink {
+        fn events(&self) -> Vec<MigrationEvent> {
+            self.events.lock().unwrap().clone()
+        }
+    }
+
+    impl EventSink for RecordingSink {
+        fn emit(&self, event: &MigrationEvent) {
+            self.events.lock().unwrap().push(event.clone());
+        }
+    }
+
+    fn new_runner(executor: Arc<InMemoryExecutor>) -> ScyllaMigrationRunner {
+        ScyllaMigrationRunner::from_executor(executor).with_event_sink(Arc::new(NoopSink))
+    }
+
+    fn new_migration(version: &str, query: &str) -> Migration {
+        new_script(MigrationKind::Versioned, version, query)
+    }
+
+    fn new_script(kind: MigrationKind, version: &str, query: &str) -> Migration {
+        let prefix = match kind {
+            MigrationKind::Versioned => "V",
+            MigrationKind::Repeatable => "R",
+            MigrationKind::Undo => "U",
+        };
+
+        Migration {
+            filename: format!("{}{}__migration.cql", prefix, version),
+            kind,
+            version: version.to_string(),
+            version_key: MigrationVersionKey::new(&VersionFormatting::Numeric, version),
+            name: "migration".to_string(),
+            content: query.to_string(),
+            queries: vec![query.to_string()],
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::config::FilenameConvention;
+use crate::migration::{Migration, MigrationKind};
+use crate::runner::AppliedMigration;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlanAction {
+    Apply,
+    /// A repeatable migration whose checksum changed since it was last applied.
+    Reapply,
+    Skip,
+    Undo,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedMigration {
+    pub action: PlanAction,
+    pub kind: MigrationKind,
+    pub version: String,
+    pub name: String,
+    pub filename: String,
+    pub checksum: String,
+    pub content: String,
+    pub statements: Vec<String>,
+}
+
+impl PlannedMigration {
+    pub(crate) fn new(action: PlanAction, migration: &Migration, checksum: String) -> Self {
+        Self {
+            action,
+            kind: migration.kind,
+            version: migration.version.clone(),
+            name: migration.name.clone(),
+            filename: migration.filename.clone(),
+            checksum,
+            content: migration.content.clone(),
+            statements: migration.queries.clone(),
+        }
+    }
+
+    pub(crate) fn to_migration(&self) -> Migration {
+        Migration {
+            filename: self.filename.clone(),
+            kind: self.kind,
+            version: self.version.clone(),
+            version_key: None,
+            name: self.name.clone(),
+            content: self.content.clone(),
+            queries: self.statements.clone(),
+        }
+    }
+}
+
+/// Ordered actions computed by `MigrationRunner::plan`, meant to be reviewed before
+/// being handed to `MigrationRunner::execute` unchanged.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationPlan {
+    pub keyspace: String,
+    /// Fingerprint of the successful history the plan was computed against.
+    pub history_checksum: String,
+    pub actions: Vec<PlannedMigration>,
+}
+
+impl MigrationPlan {
+    /// Actions that will execute statements, i.e. everything but skipped migrations.
+    pub fn pending(&self) -> impl Iterator<Item = &PlannedMigration> {
+        self.actions
+            .iter()
+            .filter(|planned| planned.action != PlanAction::Skip)
+    }
+}
+
+/// Effective state of the keyspace derived from its successful history.
+#[derive(Default)]
+pub(crate) struct AppliedState {
+    /// Versions still applied, oldest first; undone versions are removed.
+    pub versions: Vec<String>,
+    pub repeatable_checksums: HashMap<String, String>,
+}
+
+impl AppliedState {
+    /// Folds history returned by the executor, which lists the latest migration first.
+    pub fn from_history(
+        applied_migrations: &[AppliedMigration],
+        filename_convention: &FilenameConvention,
+    ) -> Self {
+        let mut state = Self::default();
+
+        for applied_migration in applied_migrations.iter().rev() {
+            match filename_convention.kind(&applied_migration.filename) {
+                Some(MigrationKind::Repeatable) => {
+                    state.repeatable_checksums.insert(
+                        applied_migration.filename.clone(),
+                        applied_migration.checksum.clone(),
+                    );
+                }
+                Some(MigrationKind::Undo) => state
+                    .versions
+                    .retain(|version| *version != applied_migration.version),
+                _ => state.versions.push(applied_migration.version.clone()),
+            }
+        }
+
+        state
+    }
+}
+
+pub(crate) fn history_checksum(applied_migrations: &[AppliedMigration]) -> String {
+    let checksum = applied_migrations
+        .iter()
+        .fold(Sha256::new(), |hasher, applied_migration| {
+            hasher
+                .chain_update(applied_migration.id.as_bytes())
+                .chain_update(applied_migration.checksum.as_bytes())
+        })
+        .finalize();
+
+    format!("{:x}", checksum)
+}
@@ -0,0 +1,111 @@
+use crate::config::{ConnectionConfig, TlsConfig};
+use crate::session::SessionError::*;
+use scylla::load_balancing::DefaultPolicy;
+use scylla::transport::errors::NewSessionError;
+use scylla::{ExecutionProfile, Session, SessionBuilder};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("TLS could not be configured from {0}: {1}")]
+    TlsError(String, String),
+
+    #[error("TLS is configured but flowfine was built without the tls feature")]
+    TlsUnsupportedError,
+
+    #[error("Could not connect to the cluster: {0}")]
+    ConnectionError(#[from] NewSessionError),
+}
+
+/// Connects to the cluster described by `connection`.
+pub async fn build_session(connection: &ConnectionConfig) -> Result<Session, SessionError> {
+    Ok(session_builder(connection)?.build().await?)
+}
+
+/// Prepares a builder with contact points, credentials, TLS and load balancing,
+/// leaving room for further driver options before connecting.
+pub fn session_builder(connection: &ConnectionConfig) -> Result<SessionBuilder, SessionError> {
+    let mut builder = SessionBuilder::new().known_nodes(&connection.nodes);
+
+    if let (Some(username), Some(password)) = (&connection.username, &connection.password) {
+        builder = builder.user(username, password);
+    }
+
+    if let Some(local_datacenter) = &connection.local_datacenter {
+        let policy = DefaultPolicy::builder()
+            .prefer_datacenter(local_datacenter.clone())
+            .token_aware(true)
+            .build();
+        let profile = ExecutionProfile::builder()
+            .load_balancing_policy(policy)
+            .build();
+        builder = builder.default_execution_profile_handle(profile.into_handle());
+    }
+
+    if let Some(tls) = &connection.tls {
+        builder = with_tls(builder, tls)?;
+    }
+
+    Ok(builder)
+}
+
+#[cfg(feature = "tls")]
+fn with_tls(builder: SessionBuilder, tls: &TlsConfig) -> Result<SessionBuilder, SessionError> {
+    use openssl::ssl::{SslContextBuilder, SslFiletype, SslMethod, SslVerifyMode};
+
+    let tls_error =
+        |source: String| move |err: openssl::error::ErrorStack| TlsError(source, err.to_string());
+    let file_error = |path: &std::path::PathBuf| tls_error(path.display().to_string());
+
+    let mut context =
+        SslContextBuilder::new(SslMethod::tls()).map_err(tls_error("OpenSSL".to_string()))?;
+    context.set_verify(SslVerifyMode::PEER);
+
+    match &tls.ca_file {
+        Some(ca_file) => context.set_ca_file(ca_file).map_err(file_error(ca_file))?,
+        None => context
+            .set_default_verify_paths()
+            .map_err(tls_error("system certificates".to_string()))?,
+    }
+
+    if let (Some(cert_file), Some(key_file)) = (&tls.cert_file, &tls.key_file) {
+        context
+            .set_certificate_chain_file(cert_file)
+            .map_err(file_error(cert_file))?;
+        context
+            .set_private_key_file(key_file, SslFiletype::PEM)
+            .map_err(file_error(key_file))?;
+        context.check_private_key().map_err(file_error(key_file))?;
+    }
+
+    Ok(builder.ssl_context(Some(context.build())))
+}
+
+#[cfg(not(feature = "tls"))]
+fn with_tls(_builder: SessionBuilder, _tls: &TlsConfig) -> Result<SessionBuilder, SessionError> {
+    Err(TlsUnsupportedError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_reject_missing_ca_file() {
+        // given
+        let connection = ConnectionConfig {
+            tls: Some(TlsConfig {
+                ca_file: Some(PathBuf::from("./tests/data/missing-ca.pem")),
+                ..TlsConfig::default()
+            }),
+            ..ConnectionConfig::default()
+        };
+
+        // when
+        let result = session_builder(&connection);
+
+        // then
+        assert!(matches!(result, Err(TlsError(source, _)) if source.ends_with("missing-ca.pem")));
+    }
+}
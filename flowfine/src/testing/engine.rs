@@ -0,0 +1,826 @@
+use crate::testing::frame::BoundValues;
+use crate::testing::statement::*;
+use crate::testing::value::{ColumnType, Value};
+use chrono::Utc;
+use scylla::transport::partitioner::{Murmur3Partitioner, Partitioner};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+const HOST_ID: [u8; 16] = [
+    0x0f, 0x10, 0xf1, 0x0e, 0, 0, 0x40, 0, 0x80, 0, 0, 0, 0, 0, 0, 1,
+];
+const SCHEMA_VERSION: [u8; 16] = [
+    0x0f, 0x10, 0xf1, 0x0e, 0, 0, 0x40, 0, 0x80, 0, 0, 0, 0, 0, 0, 2,
+];
+
+#[derive(Debug, Error)]
+pub enum ExecutionError {
+    #[error(transparent)]
+    Syntax(#[from] SyntaxError),
+
+    #[error("{0}")]
+    Invalid(String),
+}
+
+pub struct ResultSet {
+    pub keyspace: String,
+    pub table: String,
+    pub columns: Vec<(String, ColumnType)>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+pub enum QueryOutcome {
+    Void,
+    Rows(ResultSet),
+    SetKeyspace(String),
+    SchemaChange {
+        change: &'static str,
+        keyspace: String,
+        table: Option<String>,
+    },
+}
+
+struct Keyspace {
+    replication: Vec<(String, String)>,
+    durable_writes: bool,
+    tables: BTreeMap<String, Table>,
+}
+
+struct Table {
+    definition: TableDefinition,
+    rows: Vec<Row>,
+}
+
+#[derive(Clone)]
+struct Row {
+    values: HashMap<String, Value>,
+    expires_at: Option<Instant>,
+}
+
+/// In-memory state shared by every connection of the fake server.
+#[derive(Default)]
+pub struct Database {
+    keyspaces: BTreeMap<String, Keyspace>,
+    prepared: HashMap<Vec<u8>, String>,
+    statements: Vec<String>,
+}
+
+impl Database {
+    pub fn prepare(&mut self, statement: &str) -> Result<(Vec<u8>, usize), ExecutionError> {
+        let markers = count_markers(statement)?;
+        let id = Sha256::digest(statement.as_bytes())[..16].to_vec();
+        self.prepared.insert(id.clone(), statement.to_string());
+
+        Ok((id, markers))
+    }
+
+    pub fn prepared(&self, id: &[u8]) -> Option<String> {
+        self.prepared.get(id).cloned()
+    }
+
+    pub fn statements(&self) -> Vec<String> {
+        self.statements.clone()
+    }
+
+    pub fn execute(
+        &mut self,
+        current_keyspace: Option<&str>,
+        statement: &str,
+        values: &BoundValues,
+    ) -> Result<QueryOutcome, ExecutionError> {
+        let parsed = parse_statement(statement)?;
+        let context = Context {
+            current_keyspace,
+            values,
+        };
+
+        if !is_internal(&parsed) {
+            self.statements.push(statement.trim().to_string());
+        }
+
+        match parsed {
+            Statement::Use(keyspace) => {
+                if !self.keyspaces.contains_key(&keyspace) && !keyspace.starts_with("system") {
+                    return Err(invalid(format!("Keyspace '{}' does not exist", keyspace)));
+                }
+                Ok(QueryOutcome::SetKeyspace(keyspace))
+            }
+            Statement::CreateKeyspace {
+                keyspace,
+                if_not_exists,
+                replication,
+                durable_writes,
+            } => {
+                if self.keyspaces.contains_key(&keyspace) {
+                    return match if_not_exists {
+                        true => Ok(QueryOutcome::Void),
+                        false => Err(invalid(format!("Keyspace {} already exists", keyspace))),
+                    };
+                }
+
+                self.keyspaces.insert(
+                    keyspace.clone(),
+                    Keyspace {
+                        replication,
+                        durable_writes,
+                        tables: BTreeMap::new(),
+                    },
+                );
+                Ok(schema_change("CREATED", keyspace, None))
+            }
+            Statement::DropKeyspace {
+                keyspace,
+                if_exists,
+            } => match self.keyspaces.remove(&keyspace) {
+                Some(_) => Ok(schema_change("DROPPED", keyspace, None)),
+                None if if_exists => Ok(QueryOutcome::Void),
+                None => Err(invalid(format!("Keyspace {} does not exist", keyspace))),
+            },
+            Statement::CreateTable {
+                table,
+                if_not_exists,
+                definition,
+            } => {
+                let (keyspace_name, table_name) = context.resolve(&table)?;
+                let keyspace = self.keyspace_mut(&keyspace_name)?;
+
+                if keyspace.tables.contains_key(&table_name) {
+                    return match if_not_exists {
+                        true => Ok(QueryOutcome::Void),
+                        false => Err(invalid(format!("Table {} already exists", table_name))),
+                    };
+                }
+
+                keyspace.tables.insert(
+                    table_name.clone(),
+                    Table {
+                        definition,
+                        rows: Vec::new(),
+                    },
+                );
+                Ok(schema_change("CREATED", keyspace_name, Some(table_name)))
+            }
+            Statement::DropTable { table, if_exists } => {
+                let (keyspace_name, table_name) = context.resolve(&table)?;
+
+                match self
+                    .keyspace_mut(&keyspace_name)?
+                    .tables
+                    .remove(&table_name)
+                {
+                    Some(_) => Ok(schema_change("DROPPED", keyspace_name, Some(table_name))),
+                    None if if_exists => Ok(QueryOutcome::Void),
+                    None => Err(invalid(format!("Table {} does not exist", table_name))),
+                }
+            }
+            Statement::AlterTableAdd { table, columns } => {
+                let (keyspace_name, table_name) = context.resolve(&table)?;
+                let table = self.table_mut(&keyspace_name, &table_name)?;
+
+                for (column, column_type) in columns {
+                    if table.column_type(&column).is_ok() {
+                        return Err(invalid(format!(
+                            "Invalid column name {} because it conflicts with an existing column",
+                            column
+                        )));
+                    }
+                    table.definition.columns.push((column, column_type));
+                }
+                Ok(schema_change("UPDATED", keyspace_name, Some(table_name)))
+            }
+            Statement::Truncate(table) => {
+                let (keyspace_name, table_name) = context.resolve(&table)?;
+                self.table_mut(&keyspace_name, &table_name)?.rows.clear();
+                Ok(QueryOutcome::Void)
+            }
+            Statement::Insert {
+                table,
+                columns,
+                values,
+                if_not_exists,
+                ttl,
+            } => {
+                let (keyspace_name, table_name) = context.resolve(&table)?;
+                let table = self.table_mut(&keyspace_name, &table_name)?;
+                let outcome = table.insert(&context, &columns, &values, if_not_exists, ttl)?;
+                Ok(outcome.into_result(keyspace_name, table_name))
+            }
+            Statement::Select {
+                table,
+                columns,
+                conditions,
+                limit,
+            } => {
+                let (keyspace_name, table_name) = context.resolve(&table)?;
+                let result_set = match self.virtual_table(&keyspace_name, &table_name, &columns) {
+                    Some(mut table) => table.select(&context, columns, &conditions, limit)?,
+                    None => self.table_mut(&keyspace_name, &table_name)?.select(
+                        &context,
+                        columns,
+                        &conditions,
+                        limit,
+                    )?,
+                };
+                Ok(QueryOutcome::Rows(ResultSet {
+                    keyspace: keyspace_name,
+                    table: table_name,
+                    ..result_set
+                }))
+            }
+            Statement::Delete {
+                table,
+                conditions,
+                if_conditions,
+                if_exists,
+            } => {
+                let (keyspace_name, table_name) = context.resolve(&table)?;
+                let table = self.table_mut(&keyspace_name, &table_name)?;
+                let outcome = table.delete(&context, &conditions, &if_conditions, if_exists)?;
+                Ok(outcome.into_result(keyspace_name, table_name))
+            }
+            Statement::PassThrough => Ok(QueryOutcome::Void),
+        }
+    }
+
+    fn keyspace_mut(&mut self, keyspace: &str) -> Result<&mut Keyspace, ExecutionError> {
+        self.keyspaces
+            .get_mut(keyspace)
+            .ok_or_else(|| invalid(format!("Keyspace {} does not exist", keyspace)))
+    }
+
+    fn table_mut(&mut self, keyspace: &str, table: &str) -> Result<&mut Table, ExecutionError> {
+        self.keyspace_mut(keyspace)?
+            .tables
+            .get_mut(table)
+            .ok_or_else(|| invalid(format!("unconfigured table {}", table)))
+    }
+
+    /// Builds the system tables the driver and the runner read from.
+    fn virtual_table(
+        &self,
+        keyspace: &str,
+        table: &str,
+        selected: &Option<Vec<String>>,
+    ) -> Option<Table> {
+        let table = match (keyspace, table) {
+            ("system", "local") => Table::new(
+                &[
+                    ("key", ColumnType::Text),
+                    ("host_id", ColumnType::Uuid),
+                    ("rpc_address", ColumnType::Inet),
+                    ("data_center", ColumnType::Text),
+                    ("rack", ColumnType::Text),
+                    ("tokens", ColumnType::Set(Box::new(ColumnType::Text))),
+                    ("schema_version", ColumnType::Uuid),
+                    ("cluster_name", ColumnType::Text),
+                    ("release_version", ColumnType::Text),
+                    ("partitioner", ColumnType::Text),
+                ],
+                vec![vec![
+                    Value::Text("local".to_string()),
+                    Value::Blob(HOST_ID.to_vec()),
+                    Value::Inet(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+                    Value::Text("datacenter1".to_string()),
+                    Value::Text("rack1".to_string()),
+                    Value::List(vec![Value::Text("0".to_string())]),
+                    Value::Blob(SCHEMA_VERSION.to_vec()),
+                    Value::Text("flowfine".to_string()),
+                    Value::Text("3.0.8".to_string()),
+                    Value::Text("org.apache.cassandra.dht.Murmur3Partitioner".to_string()),
+                ]],
+            ),
+            ("system", "peers") => Table::new(
+                &[
+                    ("peer", ColumnType::Inet),
+                    ("host_id", ColumnType::Uuid),
+                    ("rpc_address", ColumnType::Inet),
+                    ("data_center", ColumnType::Text),
+                    ("rack", ColumnType::Text),
+                    ("tokens", ColumnType::Set(Box::new(ColumnType::Text))),
+                    ("schema_version", ColumnType::Uuid),
+                ],
+                Vec::new(),
+            ),
+            ("system_schema", "keyspaces") => Table::new(
+                &[
+                    ("keyspace_name", ColumnType::Text),
+                    ("durable_writes", ColumnType::Boolean),
+                    (
+                        "replication",
+                        ColumnType::Map(Box::new(ColumnType::Text), Box::new(ColumnType::Text)),
+                    ),
+                ],
+                self.keyspaces
+                    .iter()
+                    .map(|(name, keyspace)| {
+                        vec![
+                            Value::Text(name.clone()),
+                            Value::Boolean(keyspace.durable_writes),
+                            Value::Map(
+                                keyspace
+                                    .replication
+                                    .iter()
+                                    .map(|(key, value)| {
+                                        (Value::Text(key.clone()), Value::Text(value.clone()))
+                                    })
+                                    .collect(),
+                            ),
+                        ]
+                    })
+                    .collect(),
+            ),
+            ("system_schema", "tables") => Table::new(
+                &[
+                    ("keyspace_name", ColumnType::Text),
+                    ("table_name", ColumnType::Text),
+                ],
+                self.keyspaces
+                    .iter()
+                    .flat_map(|(keyspace_name, keyspace)| {
+                        keyspace.tables.keys().map(move |table_name| {
+                            vec![
+                                Value::Text(keyspace_name.clone()),
+                                Value::Text(table_name.clone()),
+                            ]
+                        })
+                    })
+                    .collect(),
+            ),
+            ("system_schema", "columns") => Table::new(
+                &[
+                    ("keyspace_name", ColumnType::Text),
+                    ("table_name", ColumnType::Text),
+                    ("column_name", ColumnType::Text),
+                    ("kind", ColumnType::Text),
+                    ("position", ColumnType::Int),
+                    ("type", ColumnType::Text),
+                ],
+                self.keyspaces
+                    .iter()
+                    .flat_map(|(keyspace_name, keyspace)| {
+                        keyspace.tables.iter().flat_map(move |(table_name, table)| {
+                            table.column_rows(keyspace_name, table_name)
+                        })
+                    })
+                    .collect(),
+            ),
+            (keyspace, _) if keyspace.starts_with("system") => {
+                let columns = selected
+                    .iter()
+                    .flatten()
+                    .map(|column| (column.as_str(), ColumnType::Text))
+                    .collect::<Vec<_>>();
+                Table::new(&columns, Vec::new())
+            }
+            _ => return None,
+        };
+
+        Some(table)
+    }
+}
+
+struct Context<'a> {
+    current_keyspace: Option<&'a str>,
+    values: &'a BoundValues,
+}
+
+impl Context<'_> {
+    fn resolve(&self, table: &TableName) -> Result<(String, String), ExecutionError> {
+        let keyspace = table
+            .keyspace
+            .as_deref()
+            .or(self.current_keyspace)
+            .ok_or_else(|| invalid("No keyspace has been specified".to_string()))?;
+
+        Ok((keyspace.to_string(), table.table.clone()))
+    }
+
+    fn evaluate(&self, term: &Term, column_type: &ColumnType) -> Result<Value, ExecutionError> {
+        let value = match term {
+            Term::Function(name, arguments) => match (name.as_str(), arguments.as_slice()) {
+                ("totimestamp", [Term::Function(now, now_arguments)])
+                    if now == "now" && now_arguments.is_empty() =>
+                {
+                    Some(Value::BigInt(Utc::now().timestamp_millis()))
+                }
+                _ => return Err(invalid(format!("Unknown function {}", name))),
+            },
+            Term::Marker(index) => match self.values.get(*index) {
+                Some(Some(bytes)) => column_type.decode(bytes),
+                Some(None) => Some(Value::Null),
+                None => return Err(invalid(format!("Missing value for bind marker {}", index))),
+            },
+            Term::Literal(literal) => column_type.parse_literal(literal),
+        };
+
+        value.ok_or_else(|| invalid(format!("Invalid value for type {:?}", column_type)))
+    }
+}
+
+enum LwtOutcome {
+    NotConditional,
+    Applied(bool, Vec<(String, ColumnType, Value)>),
+}
+
+impl LwtOutcome {
+    fn into_result(self, keyspace: String, table: String) -> QueryOutcome {
+        match self {
+            LwtOutcome::NotConditional => QueryOutcome::Void,
+            LwtOutcome::Applied(applied, current) => {
+                let mut columns = vec![("[applied]".to_string(), ColumnType::Boolean)];
+                let mut row = vec![Value::Boolean(applied)];
+                for (name, column_type, value) in current {
+                    columns.push((name, column_type));
+                    row.push(value);
+                }
+
+                QueryOutcome::Rows(ResultSet {
+                    keyspace,
+                    table,
+                    columns,
+                    rows: vec![row],
+                })
+            }
+        }
+    }
+}
+
+impl Table {
+    fn new(columns: &[(&str, ColumnType)], rows: Vec<Vec<Value>>) -> Self {
+        let names = columns
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<_>>();
+
+        Self {
+            definition: TableDefinition {
+                columns: columns
+                    .iter()
+                    .map(|(name, column_type)| (name.to_string(), column_type.clone()))
+                    .collect(),
+                partition_key: names.iter().take(1).cloned().collect(),
+                clustering_key: Vec::new(),
+                descending: Vec::new(),
+            },
+            rows: rows
+                .into_iter()
+                .map(|values| Row {
+                    values: names.iter().cloned().zip(values).collect(),
+                    expires_at: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Describes each column the way `system_schema.columns` does.
+    fn column_rows(&self, keyspace: &str, table: &str) -> Vec<Vec<Value>> {
+        let definition = &self.definition;
+
+        definition
+            .columns
+            .iter()
+            .map(|(column, column_type)| {
+                let partition_position = definition
+                    .partition_key
+                    .iter()
+                    .position(|key| key == column);
+                let clustering_position = definition
+                    .clustering_key
+                    .iter()
+                    .position(|key| key == column);
+                let (kind, position) = match (partition_position, clustering_position) {
+                    (Some(position), _) => ("partition_key", position as i32),
+                    (None, Some(position)) => ("clustering", position as i32),
+                    (None, None) => ("regular", -1),
+                };
+
+                vec![
+                    Value::Text(keyspace.to_string()),
+                    Value::Text(table.to_string()),
+                    Value::Text(column.clone()),
+                    Value::Text(kind.to_string()),
+                    Value::Int(position),
+                    Value::Text(column_type.cql_name()),
+                ]
+            })
+            .collect()
+    }
+
+    fn column_type(&self, column: &str) -> Result<&ColumnType, ExecutionError> {
+        self.definition
+            .columns
+            .iter()
+            .find(|(name, _)| name == column)
+            .map(|(_, column_type)| column_type)
+            .ok_or_else(|| invalid(format!("Undefined column name {}", column)))
+    }
+
+    /// Murmur3 token of the partition key of `row`, composite keys being serialized the way
+    /// Cassandra does.
+    fn token(&self, row: &Row) -> i64 {
+        let values = self
+            .definition
+            .partition_key
+            .iter()
+            .map(|column| {
+                row.values
+                    .get(column)
+                    .and_then(Value::encode)
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        let key = match values.as_slice() {
+            [value] => value.clone(),
+            _ => values
+                .iter()
+                .flat_map(|value| [&(value.len() as u16).to_be_bytes()[..], value, &[0]].concat())
+                .collect(),
+        };
+
+        Murmur3Partitioner::hash(&key).value
+    }
+
+    fn primary_key(&self) -> impl Iterator<Item = &String> {
+        self.definition
+            .partition_key
+            .iter()
+            .chain(self.definition.clustering_key.iter())
+    }
+
+    fn purge_expired(&mut self) {
+        let now = Instant::now();
+        self.rows
+            .retain(|row| row.expires_at.is_none_or(|expires_at| expires_at > now));
+    }
+
+    fn matches(
+        &self,
+        context: &Context,
+        row: &Row,
+        conditions: &[Condition],
+    ) -> Result<bool, ExecutionError> {
+        for condition in conditions {
+            let (column_type, actual) = match &condition.selector {
+                Selector::Column(column) => (
+                    self.column_type(column)?,
+                    row.values.get(column).cloned().unwrap_or(Value::Null),
+                ),
+                Selector::Token(columns) if *columns == self.definition.partition_key => {
+                    (&ColumnType::BigInt, Value::BigInt(self.token(row)))
+                }
+                Selector::Token(_) => {
+                    return Err(invalid(
+                        "The token function arguments must be the partition key".to_string(),
+                    ))
+                }
+            };
+
+            let matches = match condition.operator {
+                Operator::Eq => context.evaluate(&condition.term, column_type)? == actual,
+                Operator::In => {
+                    let list_type = ColumnType::List(Box::new(column_type.clone()));
+                    match context.evaluate(&condition.term, &list_type)? {
+                        Value::List(candidates) => candidates.contains(&actual),
+                        _ => false,
+                    }
+                }
+                Operator::Gt => actual
+                    .compare(&context.evaluate(&condition.term, column_type)?)
+                    .is_gt(),
+                Operator::Ge => actual
+                    .compare(&context.evaluate(&condition.term, column_type)?)
+                    .is_ge(),
+                Operator::Lt => actual
+                    .compare(&context.evaluate(&condition.term, column_type)?)
+                    .is_lt(),
+                Operator::Le => actual
+                    .compare(&context.evaluate(&condition.term, column_type)?)
+                    .is_le(),
+            };
+
+            if !matches {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn find_rows(
+        &mut self,
+        context: &Context,
+        conditions: &[Condition],
+    ) -> Result<Vec<usize>, ExecutionError> {
+        self.purge_expired();
+
+        let mut indexes = Vec::new();
+        for (index, row) in self.rows.iter().enumerate() {
+            if self.matches(context, row, conditions)? {
+                indexes.push(index);
+            }
+        }
+
+        Ok(indexes)
+    }
+
+    fn select(
+        &mut self,
+        context: &Context,
+        columns: Option<Vec<String>>,
+        conditions: &[Condition],
+        limit: Option<Term>,
+    ) -> Result<ResultSet, ExecutionError> {
+        let selected = match columns {
+            Some(columns) => columns
+                .into_iter()
+                .map(|column| {
+                    let column_type = self.column_type(&column)?.clone();
+                    Ok((column, column_type))
+                })
+                .collect::<Result<Vec<_>, ExecutionError>>()?,
+            None => self.definition.columns.clone(),
+        };
+
+        let mut rows = self
+            .find_rows(context, conditions)?
+            .into_iter()
+            .map(|index| self.rows[index].clone())
+            .collect::<Vec<_>>();
+        rows.sort_by(|left, right| {
+            self.primary_key()
+                .map(|column| {
+                    let ordering = left.values[column].compare(&right.values[column]);
+                    match self.definition.descending.contains(column) {
+                        true => ordering.reverse(),
+                        false => ordering,
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        if let Some(limit) = limit {
+            match context.evaluate(&limit, &ColumnType::Int)? {
+                Value::Int(limit) => rows.truncate(limit.max(0) as usize),
+                _ => return Err(invalid("Invalid LIMIT".to_string())),
+            }
+        }
+
+        Ok(ResultSet {
+            keyspace: String::new(),
+            table: String::new(),
+            rows: rows
+                .into_iter()
+                .map(|row| {
+                    selected
+                        .iter()
+                        .map(|(column, _)| row.values.get(column).cloned().unwrap_or(Value::Null))
+                        .collect()
+                })
+                .collect(),
+            columns: selected,
+        })
+    }
+
+    fn insert(
+        &mut self,
+        context: &Context,
+        columns: &[String],
+        values: &[Term],
+        if_not_exists: bool,
+        ttl: Option<Term>,
+    ) -> Result<LwtOutcome, ExecutionError> {
+        if columns.len() != values.len() {
+            return Err(invalid("Unmatched column names/values".to_string()));
+        }
+
+        let mut row_values = HashMap::new();
+        for (column, term) in columns.iter().zip(values) {
+            let value = context.evaluate(term, self.column_type(column)?)?;
+            row_values.insert(column.clone(), value);
+        }
+
+        let key_conditions = self
+            .primary_key()
+            .map(|column| match row_values.get(column) {
+                Some(value) => Ok((column.clone(), value.clone())),
+                None => Err(invalid(format!("Missing primary key column {}", column))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let expires_at = match ttl {
+            Some(ttl) => match context.evaluate(&ttl, &ColumnType::Int)? {
+                Value::Int(seconds) if seconds > 0 => {
+                    Some(Instant::now() + Duration::from_secs(seconds as u64))
+                }
+                _ => None,
+            },
+            None => None,
+        };
+
+        self.purge_expired();
+        let existing = self.rows.iter().position(|row| {
+            key_conditions
+                .iter()
+                .all(|(column, value)| row.values.get(column) == Some(value))
+        });
+
+        match (existing, if_not_exists) {
+            (Some(index), true) => Ok(LwtOutcome::Applied(false, self.current_values(index))),
+            (Some(index), false) => {
+                let row = &mut self.rows[index];
+                row.values.extend(row_values);
+                row.expires_at = expires_at;
+                Ok(LwtOutcome::NotConditional)
+            }
+            (None, _) => {
+                self.rows.push(Row {
+                    values: row_values,
+                    expires_at,
+                });
+                match if_not_exists {
+                    true => Ok(LwtOutcome::Applied(true, Vec::new())),
+                    false => Ok(LwtOutcome::NotConditional),
+                }
+            }
+        }
+    }
+
+    fn delete(
+        &mut self,
+        context: &Context,
+        conditions: &[Condition],
+        if_conditions: &[Condition],
+        if_exists: bool,
+    ) -> Result<LwtOutcome, ExecutionError> {
+        let indexes = self.find_rows(context, conditions)?;
+
+        if !if_exists && if_conditions.is_empty() {
+            for index in indexes.into_iter().rev() {
+                self.rows.remove(index);
+            }
+            return Ok(LwtOutcome::NotConditional);
+        }
+
+        let index = match indexes.first() {
+            Some(index) => *index,
+            None => return Ok(LwtOutcome::Applied(false, Vec::new())),
+        };
+
+        if self.matches(context, &self.rows[index], if_conditions)? {
+            self.rows.remove(index);
+            return Ok(LwtOutcome::Applied(true, Vec::new()));
+        }
+
+        let current = self
+            .current_values(index)
+            .into_iter()
+            .filter(|(column, _, _)| {
+                if_conditions.iter().any(|condition| {
+                    matches!(&condition.selector, Selector::Column(name) if name == column)
+                })
+            })
+            .collect();
+        Ok(LwtOutcome::Applied(false, current))
+    }
+
+    fn current_values(&self, index: usize) -> Vec<(String, ColumnType, Value)> {
+        let row = &self.rows[index];
+
+        self.definition
+            .columns
+            .iter()
+            .map(|(column, column_type)| {
+                let value = row.values.get(column).cloned().unwrap_or(Value::Null);
+                (column.clone(), column_type.clone(), value)
+            })
+            .collect()
+    }
+}
+
+/// Statements issued by the driver itself, which are left out of the statement log.
+fn is_internal(statement: &Statement) -> bool {
+    match statement {
+        Statement::Use(_) => true,
+        Statement::Select { table, .. } => table
+            .keyspace
+            .as_deref()
+            .is_some_and(|keyspace| keyspace.starts_with("system")),
+        _ => false,
+    }
+}
+
+fn schema_change(change: &'static str, keyspace: String, table: Option<String>) -> QueryOutcome {
+    QueryOutcome::SchemaChange {
+        change,
+        keyspace,
+        table,
+    }
+}
+
+fn invalid(message: String) -> ExecutionError {
+    ExecutionError::Invalid(message)
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use thiserror::Error;
+
+pub const HEADER_LENGTH: usize = 9;
+pub const RESPONSE_VERSION: u8 = 0x84;
+
+pub const OPCODE_ERROR: u8 = 0x00;
+pub const OPCODE_STARTUP: u8 = 0x01;
+pub const OPCODE_READY: u8 = 0x02;
+pub const OPCODE_OPTIONS: u8 = 0x05;
+pub const OPCODE_SUPPORTED: u8 = 0x06;
+pub const OPCODE_QUERY: u8 = 0x07;
+pub const OPCODE_RESULT: u8 = 0x08;
+pub const OPCODE_PREPARE: u8 = 0x09;
+pub const OPCODE_EXECUTE: u8 = 0x0A;
+pub const OPCODE_REGISTER: u8 = 0x0B;
+
+#[derive(Debug, Error)]
+#[error("Malformed frame: {0}")]
+pub struct FrameError(pub String);
+
+pub struct FrameHeader {
+    pub stream: i16,
+    pub opcode: u8,
+    pub length: usize,
+}
+
+impl FrameHeader {
+    pub fn parse(header: &[u8; HEADER_LENGTH]) -> Result<Self, FrameError> {
+        if header[0] & 0x7F != 0x04 {
+            return Err(FrameError(format!(
+                "unsupported protocol version {}",
+                header[0] & 0x7F
+            )));
+        }
+
+        Ok(Self {
+            stream: i16::from_be_bytes([header[2], header[3]]),
+            opcode: header[4],
+            length: u32::from_be_bytes([header[5], header[6], header[7], header[8]]) as usize,
+        })
+    }
+}
+
+/// Values bound to a QUERY or EXECUTE request; `None` stands for null.
+pub type BoundValues = Vec<Option<Vec<u8>>>;
+
+/// The `<query_parameters>` of QUERY and EXECUTE requests the server acts upon.
+#[derive(Default)]
+pub struct QueryParameters {
+    pub values: BoundValues,
+    pub page_size: Option<i32>,
+    pub paging_state: Option<Vec<u8>>,
+}
+
+pub struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], FrameError> {
+        if self.buf.len() < length {
+            return Err(FrameError("unexpected end of frame".to_string()));
+        }
+
+        let (taken, rest) = self.buf.split_at(length);
+        self.buf = rest;
+        Ok(taken)
+    }
+
+    pub fn read_byte(&mut self) -> Result<u8, FrameError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn read_short(&mut self) -> Result<u16, FrameError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn read_int(&mut self) -> Result<i32, FrameError> {
+        let bytes = self.take(4)?;
+        Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn read_string(&mut self) -> Result<String, FrameError> {
+        let length = self.read_short()? as usize;
+        self.read_utf8(length)
+    }
+
+    pub fn read_long_string(&mut self) -> Result<String, FrameError> {
+        let length = self.read_int()?;
+        self.read_utf8(length.max(0) as usize)
+    }
+
+    pub fn read_bytes(&mut self) -> Result<Option<Vec<u8>>, FrameError> {
+        match self.read_int()? {
+            length if length < 0 => Ok(None),
+            length => Ok(Some(self.take(length as usize)?.to_vec())),
+        }
+    }
+
+    pub fn read_short_bytes(&mut self) -> Result<Vec<u8>, FrameError> {
+        let length = self.read_short()? as usize;
+        Ok(self.take(length)?.to_vec())
+    }
+
+    /// Reads the `<query_parameters>` of QUERY and EXECUTE requests, keeping bound values and
+    /// paging options.
+    pub fn read_query_parameters(&mut self) -> Result<QueryParameters, FrameError> {
+        let _consistency = self.read_short()?;
+        let flags = self.read_byte()?;
+        let mut parameters = QueryParameters::default();
+
+        if flags & 0x01 != 0 {
+            let count = self.read_short()?;
+            for _ in 0..count {
+                if flags & 0x40 != 0 {
+                    self.read_string()?;
+                }
+                parameters.values.push(self.read_bytes()?);
+            }
+        }
+        if flags & 0x04 != 0 {
+            parameters.page_size = Some(self.read_int()?);
+        }
+        if flags & 0x08 != 0 {
+            parameters.paging_state = self.read_bytes()?;
+        }
+
+        Ok(parameters)
+    }
+
+    fn read_utf8(&mut self, length: usize) -> Result<String, FrameError> {
+        let bytes = self.take(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|err| FrameError(err.to_string()))
+    }
+}
+
+#[derive(Default)]
+pub struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write_short(&mut self, value: u16) -> &mut Self {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    pub fn write_int(&mut self, value: i32) -> &mut Self {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    pub fn write_string(&mut self, value: &str) -> &mut Self {
+        self.write_short(value.len() as u16);
+        self.buf.extend_from_slice(value.as_bytes());
+        self
+    }
+
+    pub fn write_bytes(&mut self, value: Option<&[u8]>) -> &mut Self {
+        match value {
+            Some(value) => {
+                self.write_int(value.len() as i32);
+                self.buf.extend_from_slice(value);
+            }
+            None => {
+                self.write_int(-1);
+            }
+        }
+        self
+    }
+
+    pub fn write_short_bytes(&mut self, value: &[u8]) -> &mut Self {
+        self.write_short(value.len() as u16);
+        self.buf.extend_from_slice(value);
+        self
+    }
+
+    pub fn write_string_multimap(&mut self, map: &HashMap<String, Vec<String>>) -> &mut Self {
+        self.write_short(map.len() as u16);
+        for (key, values) in map {
+            self.write_string(key);
+            self.write_short(values.len() as u16);
+            for value in values {
+                self.write_string(value);
+            }
+        }
+        self
+    }
+
+    pub fn into_frame(self, stream: i16, opcode: u8) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(HEADER_LENGTH + self.buf.len());
+        frame.push(RESPONSE_VERSION);
+        frame.push(0);
+        frame.extend_from_slice(&stream.to_be_bytes());
+        frame.push(opcode);
+        frame.extend_from_slice(&(self.buf.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&self.buf);
+        frame
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
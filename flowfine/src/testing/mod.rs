@@ -0,0 +1,9 @@
+//! Test support for exercising migrations without a running Scylla cluster.
+
+mod engine;
+mod frame;
+mod server;
+mod statement;
+mod value;
+
+pub use server::FakeCqlServer;
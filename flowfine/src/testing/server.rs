@@ -0,0 +1,288 @@
+use crate::testing::engine::{Database, ExecutionError, QueryOutcome};
+use crate::testing::frame::*;
+use crate::testing::value::Value;
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+const ERROR_SERVER: i32 = 0x0000;
+const ERROR_PROTOCOL: i32 = 0x000A;
+const ERROR_SYNTAX: i32 = 0x2000;
+const ERROR_INVALID: i32 = 0x2200;
+const ERROR_UNPREPARED: i32 = 0x2500;
+
+/// A single-node CQL server speaking protocol v4, backed by an in-memory database.
+///
+/// It understands the subset of CQL issued by the runner and the driver, so migrations can be
+/// exercised end to end without a Scylla node. The server stops when dropped.
+pub struct FakeCqlServer {
+    address: SocketAddr,
+    database: Arc<Mutex<Database>>,
+    handle: JoinHandle<()>,
+}
+
+impl FakeCqlServer {
+    pub async fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?;
+        let database = Arc::new(Mutex::new(Database::default()));
+
+        let shared = database.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let database = shared.clone();
+                tokio::spawn(async move {
+                    // a broken connection only affects the client that owned it
+                    let _ = handle_connection(stream, database).await;
+                });
+            }
+        });
+
+        Ok(Self {
+            address,
+            database,
+            handle,
+        })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Statements received so far, excluding the driver's own system queries.
+    pub fn executed_statements(&self) -> Vec<String> {
+        self.database.lock().unwrap().statements()
+    }
+}
+
+impl Drop for FakeCqlServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    database: Arc<Mutex<Database>>,
+) -> io::Result<()> {
+    let mut keyspace = None;
+
+    loop {
+        let mut header = [0u8; HEADER_LENGTH];
+        stream.read_exact(&mut header).await?;
+
+        let header = match FrameHeader::parse(&header) {
+            Ok(header) => header,
+            Err(err) => {
+                let stream_id = i16::from_be_bytes([header[2], header[3]]);
+                let response = error(stream_id, ERROR_PROTOCOL, &err.to_string());
+                stream.write_all(&response).await?;
+                return Ok(());
+            }
+        };
+
+        let mut body = vec![0u8; header.length];
+        stream.read_exact(&mut body).await?;
+
+        let response = {
+            let mut database = database.lock().unwrap();
+            match handle_request(&header, &body, &mut keyspace, &mut database) {
+                Ok(response) => response,
+                Err(err) => error(header.stream, ERROR_PROTOCOL, &err.to_string()),
+            }
+        };
+        stream.write_all(&response).await?;
+    }
+}
+
+fn handle_request(
+    header: &FrameHeader,
+    body: &[u8],
+    keyspace: &mut Option<String>,
+    database: &mut Database,
+) -> Result<Vec<u8>, FrameError> {
+    let mut reader = Reader::new(body);
+
+    let response = match header.opcode {
+        OPCODE_OPTIONS => {
+            let supported = HashMap::from([
+                ("CQL_VERSION".to_string(), vec!["3.4.5".to_string()]),
+                ("COMPRESSION".to_string(), Vec::new()),
+            ]);
+            let mut writer = Writer::new();
+            writer.write_string_multimap(&supported);
+            writer.into_frame(header.stream, OPCODE_SUPPORTED)
+        }
+        OPCODE_STARTUP | OPCODE_REGISTER => Writer::new().into_frame(header.stream, OPCODE_READY),
+        OPCODE_QUERY => {
+            let statement = reader.read_long_string()?;
+            let parameters = reader.read_query_parameters()?;
+            execute(header.stream, &statement, &parameters, keyspace, database)
+        }
+        OPCODE_PREPARE => {
+            let statement = reader.read_long_string()?;
+            match database.prepare(&statement) {
+                Ok((id, markers)) => prepared(header.stream, &id, markers),
+                Err(err) => execution_error(header.stream, err),
+            }
+        }
+        OPCODE_EXECUTE => {
+            let id = reader.read_short_bytes()?;
+            let parameters = reader.read_query_parameters()?;
+            match database.prepared(&id) {
+                Some(statement) => {
+                    execute(header.stream, &statement, &parameters, keyspace, database)
+                }
+                None => unprepared(header.stream, &id),
+            }
+        }
+        opcode => error(
+            header.stream,
+            ERROR_SERVER,
+            &format!("Unsupported opcode {:#04x}", opcode),
+        ),
+    };
+
+    Ok(response)
+}
+
+fn execute(
+    stream: i16,
+    statement: &str,
+    parameters: &QueryParameters,
+    keyspace: &mut Option<String>,
+    database: &mut Database,
+) -> Vec<u8> {
+    let outcome = match database.execute(keyspace.as_deref(), statement, &parameters.values) {
+        Ok(outcome) => outcome,
+        Err(err) => return execution_error(stream, err),
+    };
+
+    let mut writer = Writer::new();
+    match outcome {
+        QueryOutcome::Void => {
+            writer.write_int(0x0001);
+        }
+        QueryOutcome::Rows(result_set) => {
+            let (rows, paging_state) = page(&result_set.rows, parameters);
+            writer.write_int(0x0002);
+            match &paging_state {
+                Some(paging_state) => writer
+                    .write_int(0x0003)
+                    .write_int(result_set.columns.len() as i32)
+                    .write_bytes(Some(paging_state)),
+                None => writer
+                    .write_int(0x0001)
+                    .write_int(result_set.columns.len() as i32),
+            };
+            writer
+                .write_string(&result_set.keyspace)
+                .write_string(&result_set.table);
+            for (name, column_type) in &result_set.columns {
+                writer.write_string(name);
+                column_type.write(&mut writer);
+            }
+
+            writer.write_int(rows.len() as i32);
+            for row in rows {
+                for value in row {
+                    writer.write_bytes(value.encode().as_deref());
+                }
+            }
+        }
+        QueryOutcome::SetKeyspace(name) => {
+            writer.write_int(0x0003).write_string(&name);
+            *keyspace = Some(name);
+        }
+        QueryOutcome::SchemaChange {
+            change,
+            keyspace,
+            table,
+        } => {
+            writer.write_int(0x0005).write_string(change);
+            match table {
+                Some(table) => writer
+                    .write_string("TABLE")
+                    .write_string(&keyspace)
+                    .write_string(&table),
+                None => writer.write_string("KEYSPACE").write_string(&keyspace),
+            };
+        }
+    }
+
+    writer.into_frame(stream, OPCODE_RESULT)
+}
+
+/// Rows of the page requested by `parameters`, the paging state being the offset of the next
+/// page when rows are left.
+fn page<'a>(
+    rows: &'a [Vec<Value>],
+    parameters: &QueryParameters,
+) -> (&'a [Vec<Value>], Option<Vec<u8>>) {
+    let offset = parameters
+        .paging_state
+        .as_deref()
+        .and_then(|paging_state| paging_state.try_into().ok())
+        .map_or(0, |offset| u32::from_be_bytes(offset) as usize)
+        .min(rows.len());
+    let rows = &rows[offset..];
+
+    match parameters.page_size {
+        Some(page_size) if page_size > 0 && rows.len() > page_size as usize => {
+            let next_offset = (offset + page_size as usize) as u32;
+            (
+                &rows[..page_size as usize],
+                Some(next_offset.to_be_bytes().to_vec()),
+            )
+        }
+        _ => (rows, None),
+    }
+}
+
+/// Bind markers are described as blobs, the driver serializes values without checking types.
+fn prepared(stream: i16, id: &[u8], markers: usize) -> Vec<u8> {
+    let mut writer = Writer::new();
+    writer
+        .write_int(0x0004)
+        .write_short_bytes(id)
+        .write_int(0x0001)
+        .write_int(markers as i32)
+        .write_int(0)
+        .write_string("")
+        .write_string("");
+    for index in 0..markers {
+        writer
+            .write_string(&format!("marker_{}", index))
+            .write_short(0x0003);
+    }
+
+    // result metadata is sent with every RESULT, so none is declared upfront
+    writer.write_int(0x0004).write_int(0);
+    writer.into_frame(stream, OPCODE_RESULT)
+}
+
+fn unprepared(stream: i16, id: &[u8]) -> Vec<u8> {
+    let mut writer = Writer::new();
+    writer
+        .write_int(ERROR_UNPREPARED)
+        .write_string("Prepared statement not found")
+        .write_short_bytes(id);
+    writer.into_frame(stream, OPCODE_ERROR)
+}
+
+fn execution_error(stream: i16, err: ExecutionError) -> Vec<u8> {
+    match err {
+        ExecutionError::Syntax(err) => error(stream, ERROR_SYNTAX, &err.to_string()),
+        ExecutionError::Invalid(message) => error(stream, ERROR_INVALID, &message),
+    }
+}
+
+fn error(stream: i16, code: i32, message: &str) -> Vec<u8> {
+    let mut writer = Writer::new();
+    writer.write_int(code).write_string(message);
+    writer.into_frame(stream, OPCODE_ERROR)
+}
@@ -0,0 +1,959 @@
+use crate::testing::value::ColumnType;
+use std::iter::Peekable;
+use std::vec::IntoIter;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+#[error("Unsupported statement: {0}")]
+pub struct SyntaxError(pub String);
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Identifier(String),
+    Str(String),
+    Number(String),
+    Marker(usize),
+    Symbol(char),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    Str(String),
+    Number(String),
+    Bool(bool),
+    Null,
+    List(Vec<Literal>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Term {
+    Literal(Literal),
+    Marker(usize),
+    /// A function call, e.g. `toTimestamp(now())`, its name lowercased.
+    Function(String, Vec<Term>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operator {
+    Eq,
+    In,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Selector {
+    Column(String),
+    /// `token(...)` of the partition key columns.
+    Token(Vec<String>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    pub selector: Selector,
+    pub operator: Operator,
+    pub term: Term,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableName {
+    pub keyspace: Option<String>,
+    pub table: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableDefinition {
+    pub columns: Vec<(String, ColumnType)>,
+    pub partition_key: Vec<String>,
+    pub clustering_key: Vec<String>,
+    pub descending: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Statement {
+    Use(String),
+    CreateKeyspace {
+        keyspace: String,
+        if_not_exists: bool,
+        replication: Vec<(String, String)>,
+        durable_writes: bool,
+    },
+    DropKeyspace {
+        keyspace: String,
+        if_exists: bool,
+    },
+    CreateTable {
+        table: TableName,
+        if_not_exists: bool,
+        definition: TableDefinition,
+    },
+    DropTable {
+        table: TableName,
+        if_exists: bool,
+    },
+    AlterTableAdd {
+        table: TableName,
+        columns: Vec<(String, ColumnType)>,
+    },
+    Truncate(TableName),
+    Insert {
+        table: TableName,
+        columns: Vec<String>,
+        values: Vec<Term>,
+        if_not_exists: bool,
+        ttl: Option<Term>,
+    },
+    Select {
+        table: TableName,
+        columns: Option<Vec<String>>,
+        conditions: Vec<Condition>,
+        limit: Option<Term>,
+    },
+    Delete {
+        table: TableName,
+        conditions: Vec<Condition>,
+        if_conditions: Vec<Condition>,
+        if_exists: bool,
+    },
+    /// Statements accepted without being modelled, e.g. `ALTER TABLE ... DROP` or `CREATE INDEX`.
+    PassThrough,
+}
+
+pub fn parse_statement(statement: &str) -> Result<Statement, SyntaxError> {
+    let tokens = tokenize(statement)?;
+    let mut parser = Parser {
+        tokens: tokens.into_iter().peekable(),
+        statement,
+    };
+
+    let parsed = parser.parse()?;
+    parser.accept_symbol(';');
+
+    match parser.tokens.next() {
+        None => Ok(parsed),
+        Some(_) if parsed == Statement::PassThrough => Ok(parsed),
+        Some(token) => Err(SyntaxError(format!(
+            "unexpected {:?} in {}",
+            token, statement
+        ))),
+    }
+}
+
+/// Counts the bind markers of a statement, which the PREPARE response has to describe.
+pub fn count_markers(statement: &str) -> Result<usize, SyntaxError> {
+    let tokens = tokenize(statement)?;
+    Ok(tokens
+        .iter()
+        .filter(|token| matches!(token, Token::Marker(_)))
+        .count())
+}
+
+fn tokenize(statement: &str) -> Result<Vec<Token>, SyntaxError> {
+    let mut tokens = Vec::new();
+    let mut chars = statement.chars().peekable();
+    let mut markers = 0;
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '-' if statement_rest_starts_with(&chars, "--") => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            '\'' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\'') if chars.peek() == Some(&'\'') => {
+                            chars.next();
+                            value.push('\'');
+                        }
+                        Some('\'') => break,
+                        Some(c) => value.push(c),
+                        None => return Err(SyntaxError("unterminated string".to_string())),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            value.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => value.push(c),
+                        None => return Err(SyntaxError("unterminated identifier".to_string())),
+                    }
+                }
+                tokens.push(Token::Identifier(value));
+            }
+            '?' => {
+                chars.next();
+                tokens.push(Token::Marker(markers));
+                markers += 1;
+            }
+            c if c.is_ascii_digit() || (c == '-' && next_is_digit(&chars)) => {
+                let mut value = String::new();
+                value.push(c);
+                chars.next();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '.') {
+                    value.push(c);
+                }
+                tokens.push(Token::Number(value));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut value = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    value.push(c);
+                }
+                tokens.push(Token::Identifier(value.to_lowercase()));
+            }
+            c => {
+                chars.next();
+                tokens.push(Token::Symbol(c));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn next_is_digit(chars: &Peekable<std::str::Chars>) -> bool {
+    chars.clone().nth(1).is_some_and(|c| c.is_ascii_digit())
+}
+
+fn statement_rest_starts_with(chars: &Peekable<std::str::Chars>, prefix: &str) -> bool {
+    chars.clone().take(prefix.len()).eq(prefix.chars())
+}
+
+struct Parser<'a> {
+    tokens: Peekable<IntoIter<Token>>,
+    statement: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(&mut self) -> Result<Statement, SyntaxError> {
+        let keyword = self.expect_identifier()?;
+
+        match keyword.as_str() {
+            "use" => Ok(Statement::Use(self.expect_identifier()?)),
+            "create" if self.accept_keyword("keyspace") => self.parse_create_keyspace(),
+            "create" if self.accept_keyword("table") || self.accept_keyword("columnfamily") => {
+                self.parse_create_table()
+            }
+            "drop" if self.accept_keyword("keyspace") => {
+                let if_exists = self.accept_keywords(&["if", "exists"]);
+                let keyspace = self.expect_identifier()?;
+                Ok(Statement::DropKeyspace {
+                    keyspace,
+                    if_exists,
+                })
+            }
+            "drop" if self.accept_keyword("table") => {
+                let if_exists = self.accept_keywords(&["if", "exists"]);
+                let table = self.parse_table_name()?;
+                Ok(Statement::DropTable { table, if_exists })
+            }
+            "alter" if self.accept_keyword("table") => self.parse_alter_table(),
+            "truncate" => {
+                self.accept_keyword("table");
+                Ok(Statement::Truncate(self.parse_table_name()?))
+            }
+            "insert" => self.parse_insert(),
+            "select" => self.parse_select(),
+            "delete" => self.parse_delete(),
+            "create" | "alter" | "drop" | "update" | "grant" | "revoke" | "begin" | "apply" => {
+                self.tokens.by_ref().for_each(drop);
+                Ok(Statement::PassThrough)
+            }
+            _ => Err(self.error()),
+        }
+    }
+
+    fn parse_create_keyspace(&mut self) -> Result<Statement, SyntaxError> {
+        let if_not_exists = self.accept_keywords(&["if", "not", "exists"]);
+        let keyspace = self.expect_identifier()?;
+        let mut replication = Vec::new();
+        let mut durable_writes = true;
+
+        self.expect_keyword("with")?;
+        loop {
+            match self.expect_identifier()?.as_str() {
+                "replication" => {
+                    self.expect_symbol('=')?;
+                    replication = self.parse_string_map()?;
+                }
+                "durable_writes" => {
+                    self.expect_symbol('=')?;
+                    durable_writes = self.expect_identifier()? == "true";
+                }
+                _ => return Err(self.error()),
+            }
+
+            if !self.accept_keyword("and") {
+                break;
+            }
+        }
+
+        Ok(Statement::CreateKeyspace {
+            keyspace,
+            if_not_exists,
+            replication,
+            durable_writes,
+        })
+    }
+
+    fn parse_create_table(&mut self) -> Result<Statement, SyntaxError> {
+        let if_not_exists = self.accept_keywords(&["if", "not", "exists"]);
+        let table = self.parse_table_name()?;
+        let mut definition = TableDefinition {
+            columns: Vec::new(),
+            partition_key: Vec::new(),
+            clustering_key: Vec::new(),
+            descending: Vec::new(),
+        };
+
+        self.expect_symbol('(')?;
+        loop {
+            if self.accept_keywords(&["primary", "key"]) {
+                self.parse_primary_key(&mut definition)?;
+            } else {
+                let column = self.expect_identifier()?;
+                let column_type = self.parse_column_type()?;
+                if self.accept_keywords(&["primary", "key"]) {
+                    definition.partition_key.push(column.clone());
+                }
+                definition.columns.push((column, column_type));
+            }
+
+            if !self.accept_symbol(',') {
+                break;
+            }
+        }
+        self.expect_symbol(')')?;
+
+        if self.accept_keyword("with") {
+            loop {
+                if self.accept_keywords(&["clustering", "order", "by"]) {
+                    self.expect_symbol('(')?;
+                    loop {
+                        let column = self.expect_identifier()?;
+                        if self.accept_keyword("desc") {
+                            definition.descending.push(column);
+                        } else {
+                            self.accept_keyword("asc");
+                        }
+                        if !self.accept_symbol(',') {
+                            break;
+                        }
+                    }
+                    self.expect_symbol(')')?;
+                } else {
+                    self.skip_option()?;
+                }
+
+                if !self.accept_keyword("and") {
+                    break;
+                }
+            }
+        }
+
+        Ok(Statement::CreateTable {
+            table,
+            if_not_exists,
+            definition,
+        })
+    }
+
+    fn parse_alter_table(&mut self) -> Result<Statement, SyntaxError> {
+        let table = self.parse_table_name()?;
+        if !self.accept_keyword("add") {
+            self.tokens.by_ref().for_each(drop);
+            return Ok(Statement::PassThrough);
+        }
+
+        let parenthesized = self.accept_symbol('(');
+        let mut columns = Vec::new();
+        loop {
+            let column = self.expect_identifier()?;
+            columns.push((column, self.parse_column_type()?));
+            if !parenthesized || !self.accept_symbol(',') {
+                break;
+            }
+        }
+        if parenthesized {
+            self.expect_symbol(')')?;
+        }
+
+        Ok(Statement::AlterTableAdd { table, columns })
+    }
+
+    fn parse_primary_key(&mut self, definition: &mut TableDefinition) -> Result<(), SyntaxError> {
+        self.expect_symbol('(')?;
+
+        if self.accept_symbol('(') {
+            loop {
+                definition.partition_key.push(self.expect_identifier()?);
+                if !self.accept_symbol(',') {
+                    break;
+                }
+            }
+            self.expect_symbol(')')?;
+        } else {
+            definition.partition_key.push(self.expect_identifier()?);
+        }
+
+        while self.accept_symbol(',') {
+            definition.clustering_key.push(self.expect_identifier()?);
+        }
+
+        self.expect_symbol(')')
+    }
+
+    fn parse_column_type(&mut self) -> Result<ColumnType, SyntaxError> {
+        let name = self.expect_identifier()?;
+        let column_type = match name.as_str() {
+            "ascii" => ColumnType::Ascii,
+            "bigint" | "counter" => ColumnType::BigInt,
+            "blob" => ColumnType::Blob,
+            "boolean" => ColumnType::Boolean,
+            "double" => ColumnType::Double,
+            "int" => ColumnType::Int,
+            "timestamp" => ColumnType::Timestamp,
+            "uuid" | "timeuuid" => ColumnType::Uuid,
+            "text" | "varchar" => ColumnType::Text,
+            "inet" => ColumnType::Inet,
+            "list" | "set" | "frozen" => {
+                self.expect_symbol('<')?;
+                let element = Box::new(self.parse_column_type()?);
+                self.expect_symbol('>')?;
+                match name.as_str() {
+                    "list" => ColumnType::List(element),
+                    "set" => ColumnType::Set(element),
+                    _ => *element,
+                }
+            }
+            "map" => {
+                self.expect_symbol('<')?;
+                let key = Box::new(self.parse_column_type()?);
+                self.expect_symbol(',')?;
+                let value = Box::new(self.parse_column_type()?);
+                self.expect_symbol('>')?;
+                ColumnType::Map(key, value)
+            }
+            _ => ColumnType::Blob,
+        };
+
+        Ok(column_type)
+    }
+
+    fn parse_insert(&mut self) -> Result<Statement, SyntaxError> {
+        self.expect_keyword("into")?;
+        let table = self.parse_table_name()?;
+
+        self.expect_symbol('(')?;
+        let mut columns = Vec::new();
+        loop {
+            columns.push(self.expect_identifier()?);
+            if !self.accept_symbol(',') {
+                break;
+            }
+        }
+        self.expect_symbol(')')?;
+
+        self.expect_keyword("values")?;
+        self.expect_symbol('(')?;
+        let mut values = Vec::new();
+        loop {
+            values.push(self.parse_term()?);
+            if !self.accept_symbol(',') {
+                break;
+            }
+        }
+        self.expect_symbol(')')?;
+
+        let mut if_not_exists = false;
+        let mut ttl = None;
+        loop {
+            if self.accept_keywords(&["if", "not", "exists"]) {
+                if_not_exists = true;
+            } else if self.accept_keyword("using") {
+                loop {
+                    match self.expect_identifier()?.as_str() {
+                        "ttl" => ttl = Some(self.parse_term()?),
+                        "timestamp" => {
+                            self.parse_term()?;
+                        }
+                        _ => return Err(self.error()),
+                    }
+                    if !self.accept_keyword("and") {
+                        break;
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+
+        Ok(Statement::Insert {
+            table,
+            columns,
+            values,
+            if_not_exists,
+            ttl,
+        })
+    }
+
+    fn parse_select(&mut self) -> Result<Statement, SyntaxError> {
+        let columns = if self.accept_symbol('*') {
+            None
+        } else {
+            let mut columns = Vec::new();
+            loop {
+                columns.push(self.expect_identifier()?);
+                if !self.accept_symbol(',') {
+                    break;
+                }
+            }
+            Some(columns)
+        };
+
+        self.expect_keyword("from")?;
+        let table = self.parse_table_name()?;
+        let conditions = match self.accept_keyword("where") {
+            true => self.parse_conditions()?,
+            false => Vec::new(),
+        };
+
+        let mut limit = None;
+        loop {
+            if self.accept_keyword("limit") {
+                limit = Some(self.parse_term()?);
+            } else if !self.accept_keywords(&["allow", "filtering"]) {
+                break;
+            }
+        }
+
+        Ok(Statement::Select {
+            table,
+            columns,
+            conditions,
+            limit,
+        })
+    }
+
+    fn parse_delete(&mut self) -> Result<Statement, SyntaxError> {
+        self.expect_keyword("from")?;
+        let table = self.parse_table_name()?;
+        self.expect_keyword("where")?;
+        let conditions = self.parse_conditions()?;
+
+        let mut if_conditions = Vec::new();
+        let mut if_exists = false;
+        if self.accept_keyword("if") {
+            if self.accept_keyword("exists") {
+                if_exists = true;
+            } else {
+                if_conditions = self.parse_conditions()?;
+            }
+        }
+
+        Ok(Statement::Delete {
+            table,
+            conditions,
+            if_conditions,
+            if_exists,
+        })
+    }
+
+    fn parse_conditions(&mut self) -> Result<Vec<Condition>, SyntaxError> {
+        let mut conditions = Vec::new();
+
+        loop {
+            let column = self.expect_identifier()?;
+            let selector = match column == "token" && self.accept_symbol('(') {
+                true => Selector::Token(self.parse_identifiers(')')?),
+                false => Selector::Column(column),
+            };
+            let operator = if self.accept_symbol('=') {
+                Operator::Eq
+            } else if self.accept_keyword("in") {
+                Operator::In
+            } else if self.accept_symbol('>') {
+                match self.accept_symbol('=') {
+                    true => Operator::Ge,
+                    false => Operator::Gt,
+                }
+            } else if self.accept_symbol('<') {
+                match self.accept_symbol('=') {
+                    true => Operator::Le,
+                    false => Operator::Lt,
+                }
+            } else {
+                return Err(self.error());
+            };
+
+            conditions.push(Condition {
+                selector,
+                operator,
+                term: self.parse_term()?,
+            });
+
+            if !self.accept_keyword("and") {
+                break;
+            }
+        }
+
+        Ok(conditions)
+    }
+
+    fn parse_term(&mut self) -> Result<Term, SyntaxError> {
+        match self.tokens.peek() {
+            Some(Token::Marker(index)) => {
+                let index = *index;
+                self.tokens.next();
+                Ok(Term::Marker(index))
+            }
+            Some(Token::Identifier(name))
+                if !["true", "false", "null"].contains(&name.as_str()) =>
+            {
+                let name = name.clone();
+                self.tokens.next();
+                self.expect_symbol('(')?;
+                let mut arguments = Vec::new();
+                if !self.accept_symbol(')') {
+                    loop {
+                        arguments.push(self.parse_term()?);
+                        if !self.accept_symbol(',') {
+                            break;
+                        }
+                    }
+                    self.expect_symbol(')')?;
+                }
+                Ok(Term::Function(name, arguments))
+            }
+            _ => Ok(Term::Literal(self.parse_literal()?)),
+        }
+    }
+
+    /// Parses a comma separated list of identifiers up to `close`, which is consumed.
+    fn parse_identifiers(&mut self, close: char) -> Result<Vec<String>, SyntaxError> {
+        let mut identifiers = Vec::new();
+        loop {
+            identifiers.push(self.expect_identifier()?);
+            if !self.accept_symbol(',') {
+                break;
+            }
+        }
+        self.expect_symbol(close)?;
+
+        Ok(identifiers)
+    }
+
+    fn parse_literal(&mut self) -> Result<Literal, SyntaxError> {
+        match self.tokens.next() {
+            Some(Token::Str(value)) => Ok(Literal::Str(value)),
+            Some(Token::Number(value)) => Ok(Literal::Number(value)),
+            Some(Token::Identifier(value)) if value == "true" => Ok(Literal::Bool(true)),
+            Some(Token::Identifier(value)) if value == "false" => Ok(Literal::Bool(false)),
+            Some(Token::Identifier(value)) if value == "null" => Ok(Literal::Null),
+            Some(Token::Symbol(open)) if open == '(' || open == '[' => {
+                let close = if open == '(' { ')' } else { ']' };
+                let mut values = Vec::new();
+                if !self.accept_symbol(close) {
+                    loop {
+                        values.push(self.parse_literal()?);
+                        if !self.accept_symbol(',') {
+                            break;
+                        }
+                    }
+                    self.expect_symbol(close)?;
+                }
+                Ok(Literal::List(values))
+            }
+            _ => Err(self.error()),
+        }
+    }
+
+    fn parse_string_map(&mut self) -> Result<Vec<(String, String)>, SyntaxError> {
+        let mut entries = Vec::new();
+
+        self.expect_symbol('{')?;
+        loop {
+            let key = match self.parse_literal()? {
+                Literal::Str(key) => key,
+                _ => return Err(self.error()),
+            };
+            self.expect_symbol(':')?;
+            let value = match self.parse_literal()? {
+                Literal::Str(value) | Literal::Number(value) => value,
+                Literal::Bool(value) => value.to_string(),
+                _ => return Err(self.error()),
+            };
+            entries.push((key, value));
+
+            if !self.accept_symbol(',') {
+                break;
+            }
+        }
+        self.expect_symbol('}')?;
+
+        Ok(entries)
+    }
+
+    fn parse_table_name(&mut self) -> Result<TableName, SyntaxError> {
+        let name = self.expect_identifier()?;
+
+        match self.accept_symbol('.') {
+            true => Ok(TableName {
+                keyspace: Some(name),
+                table: self.expect_identifier()?,
+            }),
+            false => Ok(TableName {
+                keyspace: None,
+                table: name,
+            }),
+        }
+    }
+
+    /// Skips a table option such as `compaction = {...}` or `default_time_to_live = 0`.
+    fn skip_option(&mut self) -> Result<(), SyntaxError> {
+        self.expect_identifier()?;
+        self.expect_symbol('=')?;
+
+        match self.tokens.peek() {
+            Some(Token::Symbol('{')) => self.parse_string_map().map(|_| ()),
+            _ => self.parse_literal().map(|_| ()),
+        }
+    }
+
+    fn expect_identifier(&mut self) -> Result<String, SyntaxError> {
+        match self.tokens.next() {
+            Some(Token::Identifier(value)) => Ok(value),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), SyntaxError> {
+        match self.accept_keyword(keyword) {
+            true => Ok(()),
+            false => Err(self.error()),
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Result<(), SyntaxError> {
+        match self.accept_symbol(symbol) {
+            true => Ok(()),
+            false => Err(self.error()),
+        }
+    }
+
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        self.tokens
+            .next_if(|token| matches!(token, Token::Identifier(value) if value == keyword))
+            .is_some()
+    }
+
+    fn accept_keywords(&mut self, keywords: &[&str]) -> bool {
+        let matches = self
+            .tokens
+            .clone()
+            .take(keywords.len())
+            .eq(keywords.iter().map(|k| Token::Identifier(k.to_string())));
+
+        if matches {
+            keywords.iter().for_each(|_| {
+                self.tokens.next();
+            });
+        }
+
+        matches
+    }
+
+    fn accept_symbol(&mut self, symbol: char) -> bool {
+        self.tokens
+            .next_if(|token| *token == Token::Symbol(symbol))
+            .is_some()
+    }
+
+    fn error(&self) -> SyntaxError {
+        SyntaxError(self.statement.trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_create_table() {
+        // given
+        let statement = "CREATE TABLE IF NOT EXISTS flowfine.flowfine_history (
+                id TEXT, success BOOLEAN, applied_at TIMESTAMP,
+                PRIMARY KEY (success, applied_at)
+            ) WITH CLUSTERING ORDER BY (applied_at DESC);";
+
+        // when
+        let parsed = parse_statement(statement);
+
+        // then
+        assert_eq!(
+            parsed,
+            Ok(Statement::CreateTable {
+                table: TableName {
+                    keyspace: Some("flowfine".to_string()),
+                    table: "flowfine_history".to_string(),
+                },
+                if_not_exists: true,
+                definition: TableDefinition {
+                    columns: vec![
+                        ("id".to_string(), ColumnType::Text),
+                        ("success".to_string(), ColumnType::Boolean),
+                        ("applied_at".to_string(), ColumnType::Timestamp),
+                    ],
+                    partition_key: vec!["success".to_string()],
+                    clustering_key: vec!["applied_at".to_string()],
+                    descending: vec!["applied_at".to_string()],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_alter_table_add() {
+        // when
+        let parsed = parse_statement("ALTER TABLE ks.history ADD (executed_by TEXT, count INT);");
+
+        // then
+        assert_eq!(
+            parsed,
+            Ok(Statement::AlterTableAdd {
+                table: TableName {
+                    keyspace: Some("ks".to_string()),
+                    table: "history".to_string(),
+                },
+                columns: vec![
+                    ("executed_by".to_string(), ColumnType::Text),
+                    ("count".to_string(), ColumnType::Int),
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_lwt_insert() {
+        // given
+        let statement =
+            "INSERT INTO ks.lock (name, owner) VALUES (?, 'me') IF NOT EXISTS USING TTL 600;";
+
+        // when
+        let parsed = parse_statement(statement);
+
+        // then
+        assert_eq!(
+            parsed,
+            Ok(Statement::Insert {
+                table: TableName {
+                    keyspace: Some("ks".to_string()),
+                    table: "lock".to_string(),
+                },
+                columns: vec!["name".to_string(), "owner".to_string()],
+                values: vec![
+                    Term::Marker(0),
+                    Term::Literal(Literal::Str("me".to_string()))
+                ],
+                if_not_exists: true,
+                ttl: Some(Term::Literal(Literal::Number("600".to_string()))),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_token_range_select() {
+        // when
+        let parsed = parse_statement(
+            "SELECT * FROM ks.users WHERE token(tenant, id) > ? AND token(tenant, id) <= ?;",
+        );
+
+        // then
+        let token = Selector::Token(vec!["tenant".to_string(), "id".to_string()]);
+        assert_eq!(
+            parsed,
+            Ok(Statement::Select {
+                table: TableName {
+                    keyspace: Some("ks".to_string()),
+                    table: "users".to_string(),
+                },
+                columns: None,
+                conditions: vec![
+                    Condition {
+                        selector: token.clone(),
+                        operator: Operator::Gt,
+                        term: Term::Marker(0),
+                    },
+                    Condition {
+                        selector: token,
+                        operator: Operator::Le,
+                        term: Term::Marker(1),
+                    },
+                ],
+                limit: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_function_term() {
+        // when
+        let parsed =
+            parse_statement("INSERT INTO ks.runs (id, at) VALUES (1, toTimestamp(now()));");
+
+        // then
+        match parsed {
+            Ok(Statement::Insert { values, .. }) => assert_eq!(
+                values[1],
+                Term::Function(
+                    "totimestamp".to_string(),
+                    vec![Term::Function("now".to_string(), Vec::new())]
+                )
+            ),
+            other => panic!("unexpected statement {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_delete_with_condition() {
+        // when
+        let parsed = parse_statement("DELETE FROM lock WHERE name = ? IF owner = ?");
+
+        // then
+        assert_eq!(
+            parsed,
+            Ok(Statement::Delete {
+                table: TableName {
+                    keyspace: None,
+                    table: "lock".to_string(),
+                },
+                conditions: vec![Condition {
+                    selector: Selector::Column("name".to_string()),
+                    operator: Operator::Eq,
+                    term: Term::Marker(0),
+                }],
+                if_conditions: vec![Condition {
+                    selector: Selector::Column("owner".to_string()),
+                    operator: Operator::Eq,
+                    term: Term::Marker(1),
+                }],
+                if_exists: false,
+            })
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use crate::testing::frame::Writer;
+use crate::testing::statement::Literal;
+use std::cmp::Ordering;
+use std::net::IpAddr;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnType {
+    Ascii,
+    BigInt,
+    Blob,
+    Boolean,
+    Double,
+    Int,
+    Timestamp,
+    Uuid,
+    Text,
+    Inet,
+    List(Box<ColumnType>),
+    Set(Box<ColumnType>),
+    Map(Box<ColumnType>, Box<ColumnType>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Text(String),
+    BigInt(i64),
+    Int(i32),
+    Boolean(bool),
+    Double(f64),
+    Blob(Vec<u8>),
+    Inet(IpAddr),
+    List(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+}
+
+impl ColumnType {
+    /// Writes the `[option]` describing this type in result metadata.
+    pub fn write(&self, writer: &mut Writer) {
+        match self {
+            ColumnType::Ascii => writer.write_short(0x0001),
+            ColumnType::BigInt => writer.write_short(0x0002),
+            ColumnType::Blob => writer.write_short(0x0003),
+            ColumnType::Boolean => writer.write_short(0x0004),
+            ColumnType::Double => writer.write_short(0x0007),
+            ColumnType::Int => writer.write_short(0x0009),
+            ColumnType::Timestamp => writer.write_short(0x000B),
+            ColumnType::Uuid => writer.write_short(0x000C),
+            ColumnType::Text => writer.write_short(0x000D),
+            ColumnType::Inet => writer.write_short(0x0010),
+            ColumnType::List(element) => {
+                writer.write_short(0x0020);
+                element.write(writer);
+                writer
+            }
+            ColumnType::Map(key, value) => {
+                writer.write_short(0x0021);
+                key.write(writer);
+                value.write(writer);
+                writer
+            }
+            ColumnType::Set(element) => {
+                writer.write_short(0x0022);
+                element.write(writer);
+                writer
+            }
+        };
+    }
+
+    /// The type as written in CQL, e.g. `map<text, int>`, as listed by `system_schema.columns`.
+    pub fn cql_name(&self) -> String {
+        match self {
+            ColumnType::Ascii => "ascii".to_string(),
+            ColumnType::BigInt => "bigint".to_string(),
+            ColumnType::Blob => "blob".to_string(),
+            ColumnType::Boolean => "boolean".to_string(),
+            ColumnType::Double => "double".to_string(),
+            ColumnType::Int => "int".to_string(),
+            ColumnType::Timestamp => "timestamp".to_string(),
+            ColumnType::Uuid => "uuid".to_string(),
+            ColumnType::Text => "text".to_string(),
+            ColumnType::Inet => "inet".to_string(),
+            ColumnType::List(element) => format!("list<{}>", element.cql_name()),
+            ColumnType::Set(element) => format!("set<{}>", element.cql_name()),
+            ColumnType::Map(key, value) => {
+                format!("map<{}, {}>", key.cql_name(), value.cql_name())
+            }
+        }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> Option<Value> {
+        let value = match self {
+            ColumnType::Ascii | ColumnType::Text => {
+                Value::Text(String::from_utf8(bytes.to_vec()).ok()?)
+            }
+            ColumnType::BigInt | ColumnType::Timestamp => {
+                Value::BigInt(i64::from_be_bytes(bytes.try_into().ok()?))
+            }
+            ColumnType::Int => Value::Int(i32::from_be_bytes(bytes.try_into().ok()?)),
+            ColumnType::Boolean => Value::Boolean(*bytes.first()? != 0),
+            ColumnType::Double => Value::Double(f64::from_be_bytes(bytes.try_into().ok()?)),
+            ColumnType::Blob | ColumnType::Uuid => Value::Blob(bytes.to_vec()),
+            ColumnType::Inet => match bytes.len() {
+                4 => Value::Inet(IpAddr::from(<[u8; 4]>::try_from(bytes).ok()?)),
+                16 => Value::Inet(IpAddr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+                _ => return None,
+            },
+            ColumnType::List(element) | ColumnType::Set(element) => {
+                let mut reader = crate::testing::frame::Reader::new(bytes);
+                let count = reader.read_int().ok()?;
+                let mut values = Vec::new();
+                for _ in 0..count {
+                    let bytes = reader.read_bytes().ok()??;
+                    values.push(element.decode(&bytes)?);
+                }
+                Value::List(values)
+            }
+            ColumnType::Map(key_type, value_type) => {
+                let mut reader = crate::testing::frame::Reader::new(bytes);
+                let count = reader.read_int().ok()?;
+                let mut entries = Vec::new();
+                for _ in 0..count {
+                    let key = reader.read_bytes().ok()??;
+                    let value = reader.read_bytes().ok()??;
+                    entries.push((key_type.decode(&key)?, value_type.decode(&value)?));
+                }
+                Value::Map(entries)
+            }
+        };
+
+        Some(value)
+    }
+
+    pub fn parse_literal(&self, literal: &Literal) -> Option<Value> {
+        let value = match (self, literal) {
+            (_, Literal::Null) => Value::Null,
+            (ColumnType::Ascii | ColumnType::Text, Literal::Str(value)) => {
+                Value::Text(value.clone())
+            }
+            (ColumnType::BigInt | ColumnType::Timestamp, Literal::Number(value)) => {
+                Value::BigInt(value.parse().ok()?)
+            }
+            (ColumnType::Int, Literal::Number(value)) => Value::Int(value.parse().ok()?),
+            (ColumnType::Double, Literal::Number(value)) => Value::Double(value.parse().ok()?),
+            (ColumnType::Boolean, Literal::Bool(value)) => Value::Boolean(*value),
+            (ColumnType::Inet, Literal::Str(value)) => Value::Inet(value.parse().ok()?),
+            (ColumnType::List(element) | ColumnType::Set(element), Literal::List(values)) => {
+                Value::List(
+                    values
+                        .iter()
+                        .map(|value| element.parse_literal(value))
+                        .collect::<Option<_>>()?,
+                )
+            }
+            _ => return None,
+        };
+
+        Some(value)
+    }
+}
+
+impl Value {
+    pub fn encode(&self) -> Option<Vec<u8>> {
+        let bytes = match self {
+            Value::Null => return None,
+            Value::Text(value) => value.as_bytes().to_vec(),
+            Value::BigInt(value) => value.to_be_bytes().to_vec(),
+            Value::Int(value) => value.to_be_bytes().to_vec(),
+            Value::Boolean(value) => vec![*value as u8],
+            Value::Double(value) => value.to_be_bytes().to_vec(),
+            Value::Blob(value) => value.clone(),
+            Value::Inet(IpAddr::V4(value)) => value.octets().to_vec(),
+            Value::Inet(IpAddr::V6(value)) => value.octets().to_vec(),
+            Value::List(values) => {
+                let mut writer = Writer::new();
+                writer.write_int(values.len() as i32);
+                for value in values {
+                    writer.write_bytes(value.encode().as_deref());
+                }
+                writer.into_bytes()
+            }
+            Value::Map(entries) => {
+                let mut writer = Writer::new();
+                writer.write_int(entries.len() as i32);
+                for (key, value) in entries {
+                    writer.write_bytes(key.encode().as_deref());
+                    writer.write_bytes(value.encode().as_deref());
+                }
+                writer.into_bytes()
+            }
+        };
+
+        Some(bytes)
+    }
+
+    pub fn compare(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Text(left), Value::Text(right)) => left.cmp(right),
+            (Value::BigInt(left), Value::BigInt(right)) => left.cmp(right),
+            (Value::Int(left), Value::Int(right)) => left.cmp(right),
+            (Value::Boolean(left), Value::Boolean(right)) => left.cmp(right),
+            (Value::Double(left), Value::Double(right)) => left.total_cmp(right),
+            (Value::Blob(left), Value::Blob(right)) => left.cmp(right),
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Null, _) => Ordering::Less,
+            (_, Value::Null) => Ordering::Greater,
+            _ => Ordering::Equal,
+        }
+    }
+}
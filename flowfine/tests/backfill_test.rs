@@ -0,0 +1,191 @@
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use flowfine::migration::backfill::{
+        split_token_ranges, Backfill, BackfillError, RowTransform, TokenRange,
+    };
+    use flowfine::migration::rust::RustMigrationError;
+    use flowfine::testing::FakeCqlServer;
+    use lazy_static::lazy_static;
+    use rstest::{fixture, rstest};
+    use scylla::frame::response::result::Row;
+    use scylla::{Session, SessionBuilder};
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    lazy_static! {
+        static ref KEYSPACE: &'static str = "flowfine";
+        static ref TENANTS: [&'static str; 2] = ["acme", "globex"];
+        static ref USERS_PER_TENANT: i32 = 30;
+    }
+
+    #[fixture]
+    async fn server() -> FakeCqlServer {
+        FakeCqlServer::start()
+            .await
+            .expect("Failed to start fake server")
+    }
+
+    /// Connects to `server` and creates a `users` table with a composite partition key.
+    async fn connect(server: &FakeCqlServer) -> Session {
+        let session = SessionBuilder::new()
+            .known_node_addr(server.address())
+            .build()
+            .await
+            .expect("Failed to connect to fake server");
+        let statements = [
+            "CREATE KEYSPACE flowfine WITH replication = \
+                {'class': 'SimpleStrategy', 'replication_factor': 1};",
+            "CREATE TABLE flowfine.users (tenant text, id int, name text, \
+                PRIMARY KEY ((tenant, id)));",
+        ];
+        for statement in statements {
+            session.query(statement, &[]).await.unwrap();
+        }
+        for user in all_users() {
+            session
+                .query(
+                    "INSERT INTO flowfine.users (tenant, id, name) VALUES (?, ?, 'user');",
+                    user,
+                )
+                .await
+                .unwrap();
+        }
+
+        session
+    }
+
+    fn all_users() -> Vec<(String, i32)> {
+        TENANTS
+            .iter()
+            .flat_map(|tenant| (0..*USERS_PER_TENANT).map(|id| (tenant.to_string(), id)))
+            .collect()
+    }
+
+    fn backfill() -> Backfill {
+        Backfill::new("copy_users", *KEYSPACE, "users", &["tenant", "id"])
+            .with_columns(&["tenant", "id"])
+    }
+
+    /// Records the users it reads, failing instead once `fail_after` of them are recorded.
+    #[derive(Default)]
+    struct RecordingTransform {
+        users: Mutex<Vec<(String, i32)>>,
+        fail_after: Option<usize>,
+    }
+
+    impl RecordingTransform {
+        fn failing_after(fail_after: usize) -> Self {
+            Self {
+                fail_after: Some(fail_after),
+                ..Self::default()
+            }
+        }
+
+        fn users(&self) -> Vec<(String, i32)> {
+            self.users.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl RowTransform for RecordingTransform {
+        async fn transform(&self, _session: &Session, row: Row) -> Result<(), RustMigrationError> {
+            let user = row.into_typed::<(String, i32)>()?;
+            let mut users = self.users.lock().unwrap();
+            if self.fail_after == Some(users.len()) {
+                return Err("interrupted".into());
+            }
+
+            users.push(user);
+            Ok(())
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_resume_backfill_from_checkpoints(#[future] server: FakeCqlServer) {
+        let server = server.await;
+        let session = connect(&server).await;
+
+        // given
+        let backfill = backfill()
+            .with_range_count(8)
+            .with_parallelism(1)
+            .with_page_size(4);
+        let interrupted = RecordingTransform::failing_after(40);
+        let result = backfill.run(&session, &interrupted).await;
+        assert!(matches!(result, Err(BackfillError::TransformError(..))));
+
+        // when
+        let resumed = RecordingTransform::default();
+        let summary = backfill.run(&session, &resumed).await.unwrap();
+        let completed = backfill
+            .run(&session, &RecordingTransform::default())
+            .await
+            .unwrap();
+
+        // then
+        let resumed_users = resumed.users();
+        assert_eq!(summary.ranges, 8);
+        assert!(summary.resumed_ranges > 0);
+        assert_eq!(summary.rows, resumed_users.len() as u64);
+        assert!(summary.rows < all_users().len() as u64);
+
+        let unique_resumed_users = resumed_users.iter().cloned().collect::<HashSet<_>>();
+        assert_eq!(unique_resumed_users.len(), resumed_users.len());
+        let mut backfilled_users = interrupted.users().into_iter().collect::<HashSet<_>>();
+        backfilled_users.extend(unique_resumed_users);
+        assert_eq!(backfilled_users, all_users().into_iter().collect());
+
+        assert_eq!(completed.resumed_ranges, 8);
+        assert_eq!(completed.rows, 0);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_backfill_every_range_once_in_parallel(#[future] server: FakeCqlServer) {
+        let server = server.await;
+        let session = connect(&server).await;
+
+        // given
+        let backfill = backfill()
+            .with_range_count(16)
+            .with_parallelism(4)
+            .with_page_size(3);
+        let transform = RecordingTransform::default();
+
+        // when
+        let summary = backfill.run(&session, &transform).await.unwrap();
+
+        // then
+        assert_eq!(summary.ranges, 16);
+        assert_eq!(summary.resumed_ranges, 0);
+        assert_eq!(summary.rows, all_users().len() as u64);
+
+        let mut users = transform.users();
+        users.sort();
+        assert_eq!(users, all_users());
+
+        let checkpoints = session
+            .query(
+                "SELECT range_start, range_end, rows FROM flowfine.flowfine_backfill \
+                    WHERE name = ?;",
+                ("copy_users",),
+            )
+            .await
+            .unwrap()
+            .rows_typed_or_empty::<(i64, i64, i64)>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let ranges = checkpoints
+            .iter()
+            .map(|(start, end, _)| TokenRange {
+                start: *start,
+                end: *end,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, split_token_ranges(16));
+        let rows = checkpoints.iter().map(|(_, _, rows)| rows).sum::<i64>();
+        assert_eq!(rows, all_users().len() as i64);
+    }
+}
@@ -0,0 +1,223 @@
+#[cfg(test)]
+mod tests {
+    use flowfine::testing::FakeCqlServer;
+    use lazy_static::lazy_static;
+    use std::process::Output;
+    use tokio::process::Command;
+
+    lazy_static! {
+        static ref BINARY: &'static str = env!("CARGO_BIN_EXE_flowfine");
+        static ref PATH: &'static str = "./tests/data/int/numeric_migrations";
+        static ref INVALID_PATH: &'static str = "./tests/data/unit/invalid_migrations";
+    }
+
+    async fn flowfine(args: &[&str]) -> Output {
+        Command::new(*BINARY)
+            .args(args)
+            .output()
+            .await
+            .expect("Failed to run flowfine")
+    }
+
+    #[tokio::test]
+    async fn test_migrate_and_validate() {
+        // given
+        let server = FakeCqlServer::start().await.unwrap();
+        let node = server.address().to_string();
+        let args = [
+            "--node",
+            &node,
+            "--keyspace",
+            "flowfine",
+            "--location",
+            *PATH,
+            "--replication-factor",
+            "1",
+        ];
+
+        // when
+        let migrate = flowfine(&[&["migrate"], &args[..]].concat()).await;
+        let validate = flowfine(&[&["validate"], &args[..]].concat()).await;
+
+        // then
+        assert!(migrate.status.success());
+        assert!(String::from_utf8_lossy(&migrate.stdout).contains("Applied 3 migrations"));
+        assert!(validate.status.success());
+    }
+
+    #[tokio::test]
+    async fn test_migrate_with_config_profile() {
+        // given
+        let server = FakeCqlServer::start().await.unwrap();
+        let config_path =
+            std::env::temp_dir().join(format!("flowfine-{}.toml", server.address().port()));
+        std::fs::write(
+            &config_path,
+            format!(
+                "keyspace = \"flowfine\"\nlocations = [\"{}\"]\n\n[profiles.dev.connection]\nnodes = [\"{}\"]\n\n[profiles.dev.keyspace_definition]\nreplication_factor = 1\n",
+                *PATH,
+                server.address()
+            ),
+        )
+        .unwrap();
+        let config = config_path.to_string_lossy().to_string();
+
+        // when
+        let output = flowfine(&["migrate", "--config", &config, "--profile", "dev"]).await;
+        std::fs::remove_file(&config_path).unwrap();
+
+        // then
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("Applied 3 migrations"));
+    }
+
+    #[tokio::test]
+    async fn test_rebase_duplicated_versions() {
+        // given
+        let server = FakeCqlServer::start().await.unwrap();
+        let node = server.address().to_string();
+        let location =
+            std::env::temp_dir().join(format!("flowfine-rebase-{}", server.address().port()));
+        std::fs::create_dir_all(location.join("orders")).unwrap();
+        std::fs::write(
+            location.join("V1__create_users.cql"),
+            "SELECT now() FROM system.local;",
+        )
+        .unwrap();
+        std::fs::write(
+            location.join("orders/V1__create_orders.cql"),
+            "SELECT now() FROM system.local;",
+        )
+        .unwrap();
+        let location = location.to_string_lossy().to_string();
+
+        // when
+        let output = flowfine(&[
+            "rebase",
+            "--node",
+            &node,
+            "--keyspace",
+            "flowfine",
+            "--location",
+            &location,
+            "--replication-factor",
+            "1",
+        ])
+        .await;
+        let rebased = std::path::Path::new(&location)
+            .join("orders/V2__create_orders.cql")
+            .exists();
+        std::fs::remove_dir_all(&location).unwrap();
+
+        // then
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("Rebased 1 migrations"));
+        assert!(rebased);
+    }
+
+    #[tokio::test]
+    async fn test_create_parsable_migration_files() {
+        // given
+        let location = std::env::temp_dir().join(format!("flowfine-new-{}", std::process::id()));
+        std::fs::create_dir_all(&location).unwrap();
+        let location = location.to_string_lossy().to_string();
+
+        // when
+        let first = flowfine(&["new", "--location", &location, "create_users"]).await;
+        let second = flowfine(&["new", "--location", &location, "create_orders"]).await;
+        let created = std::path::Path::new(&location)
+            .join("V2__create_orders.cql")
+            .exists();
+        std::fs::remove_dir_all(&location).unwrap();
+
+        // then
+        assert!(first.status.success());
+        assert!(second.status.success());
+        assert!(created);
+    }
+
+    #[tokio::test]
+    async fn test_exit_with_validation_error() {
+        // given
+        let server = FakeCqlServer::start().await.unwrap();
+        let node = server.address().to_string();
+        let location =
+            std::env::temp_dir().join(format!("flowfine-validate-{}", server.address().port()));
+        std::fs::create_dir_all(&location).unwrap();
+        let migration = location.join("V1__create_users.cql");
+        std::fs::write(
+            &migration,
+            "CREATE TABLE flowfine.users (id int PRIMARY KEY);",
+        )
+        .unwrap();
+        let location = location.to_string_lossy().to_string();
+        let args = [
+            "--node",
+            &node,
+            "--keyspace",
+            "flowfine",
+            "--location",
+            &location,
+            "--replication-factor",
+            "1",
+        ];
+        let migrate = flowfine(&[&["migrate"], &args[..]].concat()).await;
+        std::fs::write(
+            &migration,
+            "CREATE TABLE flowfine.users (id int PRIMARY KEY, name text);",
+        )
+        .unwrap();
+
+        // when
+        let output = flowfine(&[&["validate"], &args[..]].concat()).await;
+        std::fs::remove_dir_all(&location).unwrap();
+
+        // then
+        assert!(migrate.status.success());
+        assert_eq!(output.status.code(), Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_exit_with_session_error_on_validate() {
+        // when
+        let output = flowfine(&[
+            "validate",
+            "--node",
+            "127.0.0.1:1",
+            "--keyspace",
+            "flowfine",
+            "--location",
+            *PATH,
+        ])
+        .await;
+
+        // then
+        assert_eq!(output.status.code(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_exit_with_config_error() {
+        // when
+        let output = flowfine(&["migrate", "--keyspace", "invalid-keyspace"]).await;
+
+        // then
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("keyspace"));
+    }
+
+    #[tokio::test]
+    async fn test_exit_with_parse_error() {
+        // when
+        let output = flowfine(&[
+            "migrate",
+            "--keyspace",
+            "flowfine",
+            "--location",
+            *INVALID_PATH,
+        ])
+        .await;
+
+        // then
+        assert_eq!(output.status.code(), Some(3));
+    }
+}
@@ -0,0 +1 @@
+INSERT INTO foo (id) VALUES (1);
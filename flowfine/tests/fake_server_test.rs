@@ -0,0 +1,204 @@
+#[cfg(test)]
+mod tests {
+    use flowfine::config::VersionFormatting::Numeric;
+    use flowfine::config::{ConnectionConfig, KeyspaceDefinition, Replication};
+    use flowfine::migration::parser::get_migrations;
+    use flowfine::runner::{MigrationRunner, ScyllaMigrationRunner};
+    use flowfine::session::build_session;
+    use flowfine::testing::FakeCqlServer;
+    use lazy_static::lazy_static;
+    use rstest::{fixture, rstest};
+    use scylla::{Session, SessionBuilder};
+    use std::sync::Arc;
+
+    lazy_static! {
+        static ref KEYSPACE: &'static str = "flowfine";
+        static ref PATH: &'static str = "./tests/data/int/numeric_migrations";
+        static ref APPLY_MIGRATIONS_FAILED: &'static str = "Failed to apply migrations";
+        static ref LOAD_MIGRATIONS_FAILED: &'static str = "Failed to load migrations files";
+        static ref PARSE_MIGRATIONS_FAILED: &'static str = "Failed to parse migrations";
+    }
+
+    #[fixture]
+    async fn server() -> FakeCqlServer {
+        FakeCqlServer::start()
+            .await
+            .expect("Failed to start fake server")
+    }
+
+    async fn connect(server: &FakeCqlServer) -> Arc<Session> {
+        let session = SessionBuilder::new()
+            .known_node_addr(server.address())
+            .build()
+            .await
+            .expect("Failed to connect to fake server");
+        Arc::new(session)
+    }
+
+    fn runner(session: Arc<Session>) -> ScyllaMigrationRunner {
+        ScyllaMigrationRunner::new(session, *KEYSPACE).with_keyspace_definition(
+            KeyspaceDefinition {
+                replication: Replication::SimpleStrategy {
+                    replication_factor: 1,
+                },
+                durable_writes: true,
+            },
+        )
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_apply_migrations(#[future] server: FakeCqlServer) {
+        let server = server.await;
+        let session = connect(&server).await;
+
+        // given
+        let migrations = get_migrations(*PATH, &Numeric)
+            .expect(*LOAD_MIGRATIONS_FAILED)
+            .into_result()
+            .expect(*PARSE_MIGRATIONS_FAILED);
+
+        // when
+        let applied_migrations = runner(session.clone())
+            .run(migrations.clone())
+            .await
+            .expect(*APPLY_MIGRATIONS_FAILED);
+
+        // then
+        assert_eq!(migrations.len(), applied_migrations.len());
+        assert!(applied_migrations.iter().all(|migration| migration.success));
+
+        let rows = session
+            .query("SELECT id, name FROM flowfine.test_table;", &[])
+            .await
+            .unwrap()
+            .rows_typed::<(i32, String)>()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows, vec![(1, "test1".to_string())]);
+
+        let statements = server.executed_statements();
+        assert!(statements
+            .iter()
+            .any(|statement| statement.starts_with("CREATE KEYSPACE IF NOT EXISTS flowfine")));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_ignore_already_applied_migrations(#[future] server: FakeCqlServer) {
+        let server = server.await;
+        let session = connect(&server).await;
+
+        // given
+        let migrations = get_migrations(*PATH, &Numeric)
+            .expect(*LOAD_MIGRATIONS_FAILED)
+            .into_result()
+            .expect(*PARSE_MIGRATIONS_FAILED);
+        runner(session.clone())
+            .run(migrations.clone())
+            .await
+            .expect(*APPLY_MIGRATIONS_FAILED);
+
+        // when
+        let applied_migrations = runner(session)
+            .run(migrations)
+            .await
+            .expect(*APPLY_MIGRATIONS_FAILED);
+
+        // then
+        assert!(applied_migrations.is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_upgrade_history_table_in_old_layout(#[future] server: FakeCqlServer) {
+        let server = server.await;
+        let session = connect(&server).await;
+
+        // given
+        session
+            .query(
+                "CREATE KEYSPACE flowfine WITH replication = {'class': 'SimpleStrategy', 'replication_factor': 1};",
+                &[],
+            )
+            .await
+            .unwrap();
+        session
+            .query(
+                "CREATE TABLE flowfine.flowfine_history (
+                    id TEXT, version TEXT, name TEXT, filename TEXT, checksum TEXT,
+                    success BOOLEAN, applied_at TIMESTAMP,
+                    PRIMARY KEY (success, applied_at)
+                ) WITH CLUSTERING ORDER BY (applied_at DESC);",
+                &[],
+            )
+            .await
+            .unwrap();
+        session
+            .query(
+                "INSERT INTO flowfine.flowfine_history (id, version, name, filename, checksum, success, applied_at)
+                    VALUES ('old', '0.9', 'old', 'V0.9__old.cql', 'checksum', true, 0);",
+                &[],
+            )
+            .await
+            .unwrap();
+        let migrations = get_migrations(*PATH, &Numeric)
+            .expect(*LOAD_MIGRATIONS_FAILED)
+            .into_result()
+            .expect(*PARSE_MIGRATIONS_FAILED);
+
+        // when
+        let runner = runner(session.clone());
+        let applied_migrations = runner
+            .run(migrations.clone())
+            .await
+            .expect(*APPLY_MIGRATIONS_FAILED);
+        let history = runner.history().await.unwrap();
+
+        // then
+        assert_eq!(applied_migrations.len(), migrations.len());
+        assert_eq!(history.len(), migrations.len() + 1);
+
+        let old = history.iter().find(|entry| entry.id == "old").unwrap();
+        assert_eq!(old.interrupted, None);
+        assert!(!old.is_interrupted());
+        assert_eq!(old.execution_time, None);
+        assert_eq!(old.executed_by, None);
+        assert_eq!(old.statement_count, None);
+        assert_eq!(old.flowfine_version, None);
+        assert!(history
+            .iter()
+            .filter(|entry| entry.id != "old")
+            .all(|entry| entry.statement_count.is_some() && entry.flowfine_version.is_some()));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_connect_through_local_datacenter(#[future] server: FakeCqlServer) {
+        let server = server.await;
+
+        // given
+        let connection = ConnectionConfig {
+            nodes: vec![server.address().to_string()],
+            local_datacenter: Some("datacenter1".to_string()),
+            ..ConnectionConfig::default()
+        };
+        let migrations = get_migrations(*PATH, &Numeric)
+            .expect(*LOAD_MIGRATIONS_FAILED)
+            .into_result()
+            .expect(*PARSE_MIGRATIONS_FAILED);
+
+        // when
+        let session = build_session(&connection)
+            .await
+            .expect("Failed to connect to fake server");
+        let applied_migrations = runner(Arc::new(session))
+            .run(migrations.clone())
+            .await
+            .expect(*APPLY_MIGRATIONS_FAILED);
+
+        // then
+        assert_eq!(migrations.len(), applied_migrations.len());
+    }
+}
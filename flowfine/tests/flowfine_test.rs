@@ -0,0 +1,254 @@
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use flowfine::config::VersionFormatting::{Datetime, Numeric, Semver};
+    use flowfine::config::{
+        ConnectionConfig, FilenameConvention, KeyspaceDefinition, MigrationConfig, Replication,
+        UnrecognizedFiles,
+    };
+    use flowfine::migration::embedded::{EmbeddedFilenameConvention, EmbeddedMigrations};
+    use flowfine::migration::rust::{RustMigration, RustMigrationError};
+    use flowfine::runner::event::NoopSink;
+    use flowfine::runner::plan::PlanAction;
+    use flowfine::testing::FakeCqlServer;
+    use flowfine::{Flowfine, FlowfineError};
+    use lazy_static::lazy_static;
+    use scylla::Session;
+    use std::sync::Arc;
+
+    lazy_static! {
+        static ref KEYSPACE: &'static str = "flowfine";
+        static ref PATH: &'static str = "./tests/data/int/numeric_migrations";
+        static ref INVALID_PATH: &'static str = "./tests/data/unit/invalid_migrations";
+        static ref CALLBACK_PATH: &'static str = "./tests/data/int/callback_migrations";
+    }
+
+    fn config(server: &FakeCqlServer, location: &str) -> MigrationConfig {
+        MigrationConfig {
+            locations: vec![location.to_string()],
+            ignore: Vec::new(),
+            unrecognized_files: UnrecognizedFiles::Error,
+            filename_convention: FilenameConvention::default(),
+            version_formatting: Numeric,
+            keyspace: KEYSPACE.to_string(),
+            connection: ConnectionConfig {
+                nodes: vec![server.address().to_string()],
+                ..ConnectionConfig::default()
+            },
+            keyspace_definition: Some(KeyspaceDefinition {
+                replication: Replication::SimpleStrategy {
+                    replication_factor: 1,
+                },
+                durable_writes: true,
+            }),
+            run_deadline: None,
+            migration_budget: None,
+            target: None,
+            executed_by: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_migrate_and_report_applied_migrations() {
+        // given
+        let server = FakeCqlServer::start().await.unwrap();
+        let flowfine = Flowfine::new(config(&server, *PATH)).with_event_sink(Arc::new(NoopSink));
+
+        // when
+        let applied_migrations = flowfine.migrate().await.unwrap();
+        let plan = flowfine.info().await.unwrap();
+
+        // then
+        assert_eq!(applied_migrations.len(), 3);
+        assert!(plan
+            .actions
+            .iter()
+            .all(|planned| planned.action == PlanAction::Skip));
+        assert!(flowfine.validate().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_report_plan_without_creating_schema() {
+        // given
+        let server = FakeCqlServer::start().await.unwrap();
+        let flowfine = Flowfine::new(config(&server, *PATH)).with_event_sink(Arc::new(NoopSink));
+
+        // when
+        let plan = flowfine.info().await.unwrap();
+        flowfine.rebase().await.unwrap();
+
+        // then
+        assert_eq!(plan.actions.len(), 3);
+        assert!(plan
+            .actions
+            .iter()
+            .all(|planned| planned.action == PlanAction::Apply));
+        assert!(server.executed_statements().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_migrate_embedded_migrations() {
+        // given
+        let server = FakeCqlServer::start().await.unwrap();
+        let embedded_migrations = EmbeddedMigrations::new(
+            Numeric,
+            &[
+                (
+                    "V1__create_foo.cql",
+                    "CREATE TABLE flowfine.foo (id int PRIMARY KEY);",
+                ),
+                (
+                    "V2__insert_foo.cql",
+                    "INSERT INTO flowfine.foo (id) VALUES (1);",
+                ),
+            ],
+        );
+        let flowfine = Flowfine::new(config(&server, "./missing_directory"))
+            .with_source(Arc::new(embedded_migrations))
+            .with_event_sink(Arc::new(NoopSink));
+
+        // when
+        let applied_migrations = flowfine.migrate().await.unwrap();
+
+        // then
+        assert_eq!(applied_migrations.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_parse_embedded_migrations_with_their_own_convention() {
+        // given
+        let server = FakeCqlServer::start().await.unwrap();
+        let embedded_migrations = EmbeddedMigrations::with_convention(
+            Datetime,
+            EmbeddedFilenameConvention {
+                versioned_prefix: "M",
+                separator: "-",
+                ..EmbeddedFilenameConvention::DEFAULT
+            },
+            UnrecognizedFiles::Ignore,
+            &[
+                (
+                    "M20230903141500-create_foo.cql",
+                    "CREATE TABLE flowfine.foo (id int PRIMARY KEY);",
+                ),
+                (
+                    "M20230903141501-insert_foo.cql",
+                    "INSERT INTO flowfine.foo (id) VALUES (1);",
+                ),
+                ("README.md", "# migrations"),
+            ],
+        );
+        let config = MigrationConfig {
+            version_formatting: Semver,
+            ..config(&server, "./missing_directory")
+        };
+        let flowfine = Flowfine::new(config)
+            .with_source(Arc::new(embedded_migrations))
+            .with_event_sink(Arc::new(NoopSink));
+
+        // when
+        let applied_migrations = flowfine.migrate().await.unwrap();
+
+        // then
+        let versions = applied_migrations
+            .iter()
+            .map(|applied_migration| applied_migration.version.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(versions, vec!["20230903141500", "20230903141501"]);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_rust_migration_in_version_order() {
+        // given
+        let server = FakeCqlServer::start().await.unwrap();
+        let flowfine = Flowfine::new(config(&server, *PATH))
+            .with_rust_migration(Arc::new(BackfillTestTable))
+            .with_event_sink(Arc::new(NoopSink));
+
+        // when
+        let applied_migrations = flowfine.migrate().await.unwrap();
+
+        // then
+        let filenames = applied_migrations
+            .iter()
+            .map(|applied| applied.filename.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(filenames.len(), 4);
+        assert_eq!(filenames[3], "V10__backfill_test_table.rs");
+        assert_eq!(applied_migrations[3].checksum.len(), 64);
+        assert!(flowfine.validate().await.is_ok());
+    }
+
+    struct BackfillTestTable;
+
+    #[async_trait]
+    impl RustMigration for BackfillTestTable {
+        fn version(&self) -> &str {
+            "10"
+        }
+
+        fn description(&self) -> &str {
+            "backfill test table"
+        }
+
+        fn checksum(&self) -> Option<String> {
+            Some("1".to_string())
+        }
+
+        async fn migrate(&self, session: &Session) -> Result<(), RustMigrationError> {
+            session
+                .query(
+                    "INSERT INTO flowfine.test_table (id, name) VALUES (10, 'backfilled');",
+                    &[],
+                )
+                .await?;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_callback_scripts_around_migrations() {
+        // given
+        let server = FakeCqlServer::start().await.unwrap();
+        let flowfine =
+            Flowfine::new(config(&server, *CALLBACK_PATH)).with_event_sink(Arc::new(NoopSink));
+
+        // when
+        flowfine.migrate().await.unwrap();
+
+        // then
+        let statements = server
+            .executed_statements()
+            .into_iter()
+            .filter(|statement| {
+                statement.contains("flowfine.audit") || statement.contains("flowfine.foo")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statements,
+            vec![
+                "CREATE TABLE IF NOT EXISTS flowfine.audit (id text PRIMARY KEY);",
+                "INSERT INTO flowfine.audit (id) VALUES ('before_each');",
+                "CREATE TABLE flowfine.foo (id int PRIMARY KEY);",
+                "INSERT INTO flowfine.audit (id) VALUES ('after_each');",
+                "INSERT INTO flowfine.audit (id) VALUES ('before_each');",
+                "INSERT INTO flowfine.foo (id) VALUES (1);",
+                "INSERT INTO flowfine.audit (id) VALUES ('after_each');",
+                "INSERT INTO flowfine.audit (id) VALUES ('after_migrate');",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_report_every_unparsable_migration() {
+        // given
+        let server = FakeCqlServer::start().await.unwrap();
+        let flowfine = Flowfine::new(config(&server, *INVALID_PATH));
+
+        // when
+        let result = flowfine.migrate().await;
+
+        // then
+        assert!(matches!(result, Err(FlowfineError::ParseError(errors)) if !errors.is_empty()));
+    }
+}
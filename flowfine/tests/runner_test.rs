@@ -1,15 +1,18 @@
 #[cfg(test)]
 mod tests {
-    use flowfine::config::VersionFormatting;
+    use async_trait::async_trait;
     use flowfine::config::VersionFormatting::Numeric;
+    use flowfine::config::{KeyspaceDefinition, Replication, VersionFormatting};
     use flowfine::migration::parser::get_migrations;
     use flowfine::migration::version::MigrationVersionKey;
-    use flowfine::migration::Migration;
-    use flowfine::runner::{MigrationRunner, ScyllaMigrationRunner};
+    use flowfine::migration::{Migration, MigrationKind};
+    use flowfine::runner::callback::{CallbackError, MigrationCallback};
+    use flowfine::runner::event::{EventSink, MigrationEvent};
+    use flowfine::runner::{AppliedMigration, MigrationRunner, ScyllaMigrationRunner};
     use lazy_static::lazy_static;
     use rstest::{fixture, rstest};
     use scylla::{Session, SessionBuilder};
-    use std::sync::Arc;
+    use std::sync::{Arc, Mutex};
 
     lazy_static! {
         static ref KEYSPACE: &'static str = "flowfine";
@@ -138,6 +141,172 @@ mod tests {
         assert_eq!(applied_migrations[0].name, next_migration.name);
         assert_eq!(applied_migrations[0].filename, next_migration.filename);
         assert_eq!(applied_migrations[0].success, true);
+        assert_eq!(applied_migrations[0].statement_count, Some(1));
+        assert_eq!(
+            applied_migrations[0].flowfine_version.as_deref(),
+            Some(env!("CARGO_PKG_VERSION"))
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_invoke_callbacks(#[future] session: Arc<Session>) {
+        let session = session.await;
+        before_each(session.clone()).await;
+
+        // given
+        let callback = Arc::new(RecordingCallback::default());
+        let runner = ScyllaMigrationRunner::new(session, *KEYSPACE).with_callback(callback.clone());
+        let migrations = get_migrations(*PATH, &Numeric)
+            .expect(*LOAD_MIGRATIONS_FAILED)
+            .into_result()
+            .expect(*PARSE_MIGRATIONS_FAILED);
+
+        // when
+        runner
+            .run(migrations)
+            .await
+            .expect(*APPLY_MIGRATIONS_FAILED);
+
+        // then
+        assert_eq!(
+            *callback.events.lock().unwrap(),
+            vec![
+                "before_migrate",
+                "before_each",
+                "after_each",
+                "before_each",
+                "after_each",
+                "before_each",
+                "after_each",
+                "after_migrate",
+            ]
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_emit_events(#[future] session: Arc<Session>) {
+        let session = session.await;
+        before_each(session.clone()).await;
+
+        // given
+        let sink = Arc::new(RecordingSink::default());
+        let runner = ScyllaMigrationRunner::new(session, *KEYSPACE).with_event_sink(sink.clone());
+        let migrations = get_migrations(*PATH, &Numeric)
+            .expect(*LOAD_MIGRATIONS_FAILED)
+            .into_result()
+            .expect(*PARSE_MIGRATIONS_FAILED);
+
+        // when
+        runner
+            .run(migrations.clone())
+            .await
+            .expect(*APPLY_MIGRATIONS_FAILED);
+
+        // then
+        let events = sink.events.lock().unwrap();
+        let finished = events
+            .iter()
+            .filter(|event| matches!(event, MigrationEvent::MigrationFinished { .. }))
+            .count();
+
+        assert!(matches!(events[0], MigrationEvent::RunStarted { .. }));
+        assert!(matches!(
+            events.last(),
+            Some(MigrationEvent::RunFinished { applied: 3, .. })
+        ));
+        assert_eq!(finished, migrations.len());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_create_missing_keyspace(#[future] session: Arc<Session>) {
+        let session = session.await;
+        let drop_query = format!("DROP KEYSPACE IF EXISTS {};", *KEYSPACE);
+        session
+            .query(drop_query, &[])
+            .await
+            .expect("Failed to drop keyspace");
+
+        // given
+        let keyspace_definition = KeyspaceDefinition {
+            replication: Replication::SimpleStrategy {
+                replication_factor: 1,
+            },
+            durable_writes: true,
+        };
+        let runner = ScyllaMigrationRunner::new(session, *KEYSPACE)
+            .with_keyspace_definition(keyspace_definition);
+        let migrations = get_migrations(*PATH, &Numeric)
+            .expect(*LOAD_MIGRATIONS_FAILED)
+            .into_result()
+            .expect(*PARSE_MIGRATIONS_FAILED);
+
+        // when
+        let applied_migrations = runner
+            .run(migrations.clone())
+            .await
+            .expect(*APPLY_MIGRATIONS_FAILED);
+
+        // then
+        assert_eq!(migrations.len(), applied_migrations.len());
+    }
+
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<MigrationEvent>>,
+    }
+
+    impl EventSink for RecordingSink {
+        fn emit(&self, event: &MigrationEvent) {
+            self.events.lock().unwrap().push(event.clone());
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingCallback {
+        events: Mutex<Vec<&'static str>>,
+    }
+
+    #[async_trait]
+    impl MigrationCallback for RecordingCallback {
+        async fn before_migrate(
+            &self,
+            _session: &Session,
+            _migrations: &[Migration],
+        ) -> Result<(), CallbackError> {
+            self.events.lock().unwrap().push("before_migrate");
+            Ok(())
+        }
+
+        async fn before_each(
+            &self,
+            _session: &Session,
+            _migration: &Migration,
+        ) -> Result<(), CallbackError> {
+            self.events.lock().unwrap().push("before_each");
+            Ok(())
+        }
+
+        async fn after_each(
+            &self,
+            _session: &Session,
+            _migration: &Migration,
+            _applied_migration: &AppliedMigration,
+        ) -> Result<(), CallbackError> {
+            self.events.lock().unwrap().push("after_each");
+            Ok(())
+        }
+
+        async fn after_migrate(
+            &self,
+            _session: &Session,
+            _applied_migrations: &[AppliedMigration],
+        ) -> Result<(), CallbackError> {
+            self.events.lock().unwrap().push("after_migrate");
+            Ok(())
+        }
     }
 
     fn new_migration(
@@ -149,8 +318,9 @@ mod tests {
         let version_key = MigrationVersionKey::new(version_formatting, version)?;
         let migration = Migration {
             filename: format!("V{}_{}.cql", version, name),
+            kind: MigrationKind::Versioned,
             version: version.to_string(),
-            version_key,
+            version_key: Some(version_key),
             name: name.to_string(),
             content: content.to_string(),
             queries: vec![content.to_string()],
@@ -0,0 +1,163 @@
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use flowfine::config::VersionFormatting::Numeric;
+    use flowfine::migration::parser::get_migrations;
+    use flowfine::runner::event::NoopSink;
+    use flowfine::runner::in_memory::InMemoryExecutor;
+    use flowfine::runner::{MigrationRunner, ScyllaMigrationRunner};
+    use lazy_static::lazy_static;
+    use std::collections::HashMap;
+    use std::fmt::Debug;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    lazy_static! {
+        static ref KEYSPACE: &'static str = "flowfine";
+        static ref PATH: &'static str = "./tests/data/unit/numeric_migrations";
+    }
+
+    #[derive(Clone, Debug)]
+    struct RecordedSpan {
+        name: &'static str,
+        fields: HashMap<String, String>,
+    }
+
+    impl RecordedSpan {
+        fn field(&self, name: &str) -> Option<&str> {
+            self.fields.get(name).map(String::as_str)
+        }
+    }
+
+    /// Keeps every span created while it is the default subscriber.
+    #[derive(Clone, Default)]
+    struct RecordingSubscriber {
+        spans: Arc<Mutex<Vec<RecordedSpan>>>,
+        next_id: Arc<AtomicU64>,
+    }
+
+    impl RecordingSubscriber {
+        fn spans(&self, name: &str) -> Vec<RecordedSpan> {
+            self.spans
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|span| span.name == name)
+                .cloned()
+                .collect()
+        }
+    }
+
+    struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl Subscriber for RecordingSubscriber {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut fields = HashMap::new();
+            span.record(&mut FieldVisitor(&mut fields));
+            self.spans.lock().unwrap().push(RecordedSpan {
+                name: span.metadata().name(),
+                fields,
+            });
+
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::SeqCst) + 1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, _event: &Event<'_>) {}
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn test_instrument_migration_parsing() {
+        // given
+        let subscriber = RecordingSubscriber::default();
+        let _guard = tracing::subscriber::set_default(subscriber.clone());
+
+        // when
+        get_migrations(*PATH, &Numeric).unwrap();
+
+        // then
+        let spans = subscriber.spans("get_migrations");
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].field("directory_path"), Some(*PATH));
+    }
+
+    #[tokio::test]
+    async fn test_instrument_migration_run() {
+        // given
+        let subscriber = RecordingSubscriber::default();
+        let _guard = tracing::subscriber::set_default(subscriber.clone());
+        let executor = Arc::new(InMemoryExecutor::new(*KEYSPACE));
+        let runner =
+            ScyllaMigrationRunner::from_executor(executor).with_event_sink(Arc::new(NoopSink));
+        let migrations = get_migrations(*PATH, &Numeric)
+            .unwrap()
+            .into_result()
+            .unwrap();
+
+        // when
+        runner.run(migrations).await.unwrap();
+
+        // then
+        let plans = subscriber.spans("plan");
+        assert_eq!(plans.len(), 1);
+        assert_eq!(plans[0].field("keyspace"), Some(*KEYSPACE));
+        assert_eq!(plans[0].field("migrations"), Some("3"));
+
+        let runs = subscriber.spans("execute_with_cancellation");
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].field("actions"), Some("3"));
+
+        let applied = subscriber.spans("apply_migration");
+        let filenames = applied
+            .iter()
+            .map(|span| span.field("filename").unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            filenames,
+            vec![
+                "V1__migration.cql",
+                "V1.1__migration.cql",
+                "V2.0__migration.cql"
+            ]
+        );
+        assert!(applied
+            .iter()
+            .all(|span| span.field("keyspace") == Some(*KEYSPACE)));
+
+        let statements = subscriber.spans("statement");
+        assert_eq!(statements.len(), 3);
+        assert!(statements
+            .iter()
+            .all(|span| span.field("index") == Some("0")));
+
+        let histories = subscriber.spans("apply_history");
+        assert_eq!(histories.len(), 3);
+        assert!(histories
+            .iter()
+            .all(|span| span.field("outcome") == Some("Succeeded")));
+    }
+}